@@ -1,40 +1,180 @@
-use agb::input::Button;
+use agb::input::{Button, ButtonController};
+use alloc::{string::String, vec::Vec};
 
 use crate::scenario::ScenarioType;
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum ActionType {
     Attack,
     Shield,
     Jump,
 }
 
-#[derive(Clone, Copy)]
+impl ActionType {
+    pub const fn name(self) -> &'static str {
+        match self {
+            ActionType::Attack => "Attack",
+            ActionType::Shield => "Shield",
+            ActionType::Jump => "Jump",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
 pub struct Binding {
-    pub button: Button,
+    pub buttons: Button,
     pub scenario: ScenarioType,
     pub action: ActionType,
 }
 
-pub const BINDINGS: &[Binding] = &[
-    Binding {
-        button: Button::L,
-        scenario: ScenarioType::Water,
-        action: ActionType::Attack,
-    },
-    Binding {
-        button: Button::R,
-        scenario: ScenarioType::Volcano,
-        action: ActionType::Jump,
-    },
-    Binding {
-        button: Button::B,
-        scenario: ScenarioType::Swamp,
-        action: ActionType::Shield,
-    },
-    Binding {
-        button: Button::A,
-        scenario: ScenarioType::Swamp,
-        action: ActionType::Shield,
-    },
+// START is reserved for the pause menu
+pub const ASSIGNABLE_BUTTONS: &[Button] = &[
+    Button::A,
+    Button::B,
+    Button::SELECT,
+    Button::RIGHT,
+    Button::LEFT,
+    Button::UP,
+    Button::DOWN,
+    Button::R,
+    Button::L,
 ];
+
+// The buttons bound to each scenario, in registry order
+#[derive(Clone, PartialEq)]
+pub struct Bindings {
+    buttons: Vec<Button>,
+}
+
+impl Bindings {
+    pub fn new() -> Self {
+        Self {
+            buttons: ScenarioType::all()
+                .map(|scenario| scenario.def().default_buttons)
+                .collect(),
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = Binding> {
+        ScenarioType::all()
+            .zip(self.buttons.iter())
+            .map(|(scenario, &buttons)| Binding {
+                buttons,
+                scenario,
+                action: scenario.def().action,
+            })
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    // The buttons of every scenario whose action is `action`
+    pub fn buttons_for(&self, action: ActionType) -> Button {
+        self.iter()
+            .filter(|binding| binding.action == action)
+            .fold(Button::empty(), |buttons, binding| {
+                buttons | binding.buttons
+            })
+    }
+
+    // Binds `button` to `scenario` alone, or returns the scenario that
+    // already uses it
+    pub fn assign(&mut self, scenario: ScenarioType, button: Button) -> Result<(), ScenarioType> {
+        if let Some(other) = self
+            .iter()
+            .find(|binding| binding.scenario != scenario && binding.buttons.intersects(button))
+        {
+            return Err(other.scenario);
+        }
+
+        for (buttons, other) in self.buttons.iter_mut().zip(ScenarioType::all()) {
+            if other == scenario {
+                *buttons = button;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Prefixed with the count so a save survives scenarios being added
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.push(self.buttons.len() as u8);
+        for buttons in &self.buttons {
+            out.extend_from_slice(&(buttons.bits() as u16).to_le_bytes());
+        }
+    }
+
+    // Scenarios missing from the save start on their default buttons. If
+    // those clash with a remap, every binding goes back to its default.
+    pub fn decode(bytes: &[u8]) -> Option<(Self, &[u8])> {
+        let (&count, mut rest) = bytes.split_first()?;
+        let mut bindings = Self::new();
+
+        for index in 0..usize::from(count) {
+            let (bits, tail) = rest.split_first_chunk::<2>()?;
+            rest = tail;
+
+            if let Some(buttons) = bindings.buttons.get_mut(index) {
+                *buttons = Button::from_bits_truncate(u16::from_le_bytes(*bits).into());
+            }
+        }
+
+        if bindings.has_conflicts() {
+            bindings.reset();
+        }
+
+        Some((bindings, rest))
+    }
+
+    fn has_conflicts(&self) -> bool {
+        self.buttons.iter().enumerate().any(|(index, buttons)| {
+            buttons.is_empty()
+                || self.buttons[index + 1..]
+                    .iter()
+                    .any(|other| other.intersects(*buttons))
+        })
+    }
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const BUTTON_NAMES: &[(Button, &str)] = &[
+    (Button::A, "A"),
+    (Button::B, "B"),
+    (Button::SELECT, "SELECT"),
+    (Button::START, "START"),
+    (Button::RIGHT, "RIGHT"),
+    (Button::LEFT, "LEFT"),
+    (Button::UP, "UP"),
+    (Button::DOWN, "DOWN"),
+    (Button::R, "R"),
+    (Button::L, "L"),
+];
+
+pub fn button_names(buttons: Button) -> String {
+    let mut names = String::new();
+    for (button, name) in BUTTON_NAMES {
+        if buttons.contains(*button) {
+            if !names.is_empty() {
+                names.push(' ');
+            }
+            names.push_str(name);
+        }
+    }
+    names
+}
+
+pub fn held_buttons(input: &ButtonController) -> Button {
+    let mut held = Button::empty();
+    for (button, _) in BUTTON_NAMES {
+        if input.is_pressed(*button) {
+            held |= *button;
+        }
+    }
+    held
+}
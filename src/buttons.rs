@@ -1,21 +1,62 @@
-use agb::{display::object::Object, include_aseprite};
-use alloc::borrow::ToOwned;
-
-include_aseprite!(
-    mod buttons,
-    "gfx/buttons.aseprite",
-);
-
-pub fn get_buttons() -> [Object; 3] {
-    [
-        Object::new(buttons::BLUE.sprite(0))
-            .set_pos((90 - 8, 135 - 7))
-            .to_owned(),
-        Object::new(buttons::GREEN.sprite(0))
-            .set_pos((111 - 8, 135 - 7))
-            .to_owned(),
-        Object::new(buttons::RED.sprite(0))
-            .set_pos((132 - 8, 135 - 7))
-            .to_owned(),
-    ]
+use agb::{
+    display::{GraphicsFrame, font::AlignmentKind, object::Object},
+    fixnum::vec2,
+};
+use alloc::vec::Vec;
+
+use crate::{
+    binding::{Bindings, button_names},
+    label::Label,
+};
+
+// Where the middle hint sits, and how far apart the hints are
+const HINTS_CENTRE: i32 = 111;
+const HINT_SPACING: i32 = 21;
+const HINT_Y: i32 = 135;
+const HINT_LABEL_WIDTH: i32 = 40;
+
+struct Hint {
+    object: Object,
+    label: Label,
+}
+
+// A dot in each scenario's colour, labelled with the buttons bound to it
+pub struct ButtonHints {
+    hints: Vec<Hint>,
+}
+
+impl ButtonHints {
+    pub fn new(bindings: &Bindings) -> Self {
+        let count = bindings.iter().count() as i32;
+
+        let hints = bindings
+            .iter()
+            .enumerate()
+            .map(|(index, binding)| {
+                let x = HINTS_CENTRE + HINT_SPACING * (2 * index as i32 - (count - 1)) / 2;
+
+                let mut object = Object::new(binding.scenario.def().hint.sprite(0));
+                object.set_pos((x - 8, HINT_Y - 7));
+
+                let label = Label::new(
+                    &button_names(binding.buttons),
+                    vec2(x - HINT_LABEL_WIDTH / 2, HINT_Y - 12),
+                    AlignmentKind::Centre,
+                    16,
+                    HINT_LABEL_WIDTH,
+                );
+
+                Hint { object, label }
+            })
+            .collect();
+
+        Self { hints }
+    }
+
+    pub fn draw(&mut self, frame: &mut GraphicsFrame<'_>) {
+        for hint in &mut self.hints {
+            hint.label.draw(frame);
+            hint.object.show(frame);
+        }
+    }
 }
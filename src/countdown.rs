@@ -1,51 +1,115 @@
 use agb::timer::{Divider, Timer};
 
-pub struct Countdown<'a> {
-    seconds: usize,
-    seconds_left: usize,
+pub const TICKS_PER_SECOND: u32 = 0x4000;
+
+pub struct Clock<'a> {
     t2: &'a mut Timer,
     t3: &'a mut Timer,
     last_ticks: u32,
-    acc: u32,
 }
 
-impl<'a> Countdown<'a> {
-    pub fn new(seconds: usize, t2: &'a mut Timer, t3: &'a mut Timer) -> Self {
+impl<'a> Clock<'a> {
+    pub fn new(t2: &'a mut Timer, t3: &'a mut Timer) -> Self {
         t2.set_divider(Divider::Divider1024).set_enabled(true);
         t3.set_cascade(true).set_enabled(true);
 
         Self {
-            seconds,
-            seconds_left: 0,
             t2,
             t3,
             last_ticks: 0,
-            acc: 0,
         }
     }
 
-    pub const fn seconds_left(&self) -> usize {
-        self.seconds_left
+    fn ticks(&self) -> u32 {
+        ((self.t3.value() as u32) << 16) | (self.t2.value() as u32)
     }
 
     pub fn reset(&mut self) {
-        self.last_ticks = 0;
-        self.seconds_left = self.seconds;
+        self.last_ticks = self.ticks();
     }
 
-    pub fn set_enabled(&mut self, enabled: bool) {
-        self.t2.set_enabled(enabled);
-        self.t3.set_enabled(enabled);
-    }
-
-    pub fn update(&mut self) {
-        let ticks = ((self.t3.value() as u32) << 16) | (self.t2.value() as u32);
+    pub fn elapsed(&mut self) -> u32 {
+        let ticks = self.ticks();
         let delta = ticks.wrapping_sub(self.last_ticks);
         self.last_ticks = ticks;
-        self.acc = self.acc.wrapping_add(delta);
-        if self.acc >= 0x4000 {
-            self.acc -= 0x4000;
-            self.seconds_left -= 1;
+        delta
+    }
+}
+
+pub const fn ticks_to_millis(ticks: u32) -> u32 {
+    ((ticks as u64 * 1000) / TICKS_PER_SECOND as u64) as u32
+}
+
+pub const fn millis_to_ticks(millis: u32) -> u32 {
+    ((millis as u64 * TICKS_PER_SECOND as u64) / 1000) as u32
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Direction {
+    Down,
+    Up,
+}
+
+pub struct Countdown {
+    direction: Direction,
+    duration: u32,
+    ticks: u32,
+}
+
+impl Countdown {
+    pub fn new(seconds: usize) -> Self {
+        Self {
+            direction: Direction::Down,
+            duration: seconds as u32 * TICKS_PER_SECOND,
+            ticks: 0,
+        }
+    }
+
+    // Counts up from zero instead, for time-attack style modes
+    pub fn stopwatch() -> Self {
+        Self {
+            direction: Direction::Up,
+            duration: 0,
+            ticks: 0,
         }
     }
+
+    pub const fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    // Time left for a countdown, or time elapsed for a stopwatch
+    pub const fn millis(&self) -> u32 {
+        ticks_to_millis(self.ticks)
+    }
+
+    pub const fn seconds_left(&self) -> usize {
+        self.ticks.div_ceil(TICKS_PER_SECOND) as usize
+    }
+
+    pub const fn is_finished(&self) -> bool {
+        matches!(self.direction, Direction::Down) && self.ticks == 0
+    }
+
+    pub fn reset(&mut self) {
+        self.ticks = match self.direction {
+            Direction::Down => self.duration,
+            Direction::Up => 0,
+        };
+    }
+
+    pub fn add_time(&mut self, millis: u32) {
+        self.ticks = self.ticks.saturating_add(millis_to_ticks(millis));
+    }
+
+    pub fn subtract_time(&mut self, millis: u32) {
+        self.ticks = self.ticks.saturating_sub(millis_to_ticks(millis));
+    }
+
+    pub fn update(&mut self, elapsed: u32) {
+        self.ticks = match self.direction {
+            Direction::Down => self.ticks.saturating_sub(elapsed),
+            Direction::Up => self.ticks.saturating_add(elapsed),
+        };
+    }
 }
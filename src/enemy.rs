@@ -6,28 +6,122 @@ use agb::{
     include_aseprite_256,
 };
 
+use crate::{binding::ActionType, scenario::Scenario};
+
 include_aseprite_256! {
     mod enemy_sprite,
     "gfx/enemy.aseprite"
 }
 
-pub struct Enemy<'a> {
+const SLOTS: [(i32, Priority); 4] = [
+    (121 + 60, Priority::P3),
+    (121 + 40, Priority::P2),
+    (121 + 20, Priority::P1),
+    (121, Priority::P0),
+];
+const FRONT: usize = 3;
+const ENEMY_Y: i32 = 54;
+const DEFEAT_FRAMES: usize = 16;
+
+pub enum Requirement {
+    // The scenario's own action, this many times
+    Hits(usize),
+    // These actions in order, regardless of the scenario
+    Sequence(&'static [ActionType]),
+}
+
+pub struct EnemyDef {
+    pub requirement: Requirement,
+    pub points: usize,
+}
+
+impl EnemyDef {
+    pub const fn hits(&self) -> usize {
+        match self.requirement {
+            Requirement::Hits(hits) => hits,
+            Requirement::Sequence(actions) => actions.len(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum EnemyKind {
+    BigRock,
+    Lercio,
+    Goblin,
+    Mage,
+}
+
+impl EnemyKind {
+    pub const fn def(self) -> &'static EnemyDef {
+        match self {
+            EnemyKind::BigRock => &EnemyDef {
+                requirement: Requirement::Hits(2),
+                points: 3,
+            },
+            EnemyKind::Lercio => &EnemyDef {
+                requirement: Requirement::Hits(1),
+                points: 1,
+            },
+            EnemyKind::Goblin => &EnemyDef {
+                requirement: Requirement::Hits(1),
+                points: 1,
+            },
+            EnemyKind::Mage => &EnemyDef {
+                requirement: Requirement::Sequence(&[ActionType::Shield, ActionType::Attack]),
+                points: 4,
+            },
+        }
+    }
+
+    const fn idle_tag(self) -> &'static Tag {
+        match self {
+            EnemyKind::BigRock => &enemy_sprite::BIGROCKIDLE,
+            EnemyKind::Lercio => &enemy_sprite::LERCIOIDLE,
+            EnemyKind::Goblin => &enemy_sprite::GOBLINIDLE,
+            EnemyKind::Mage => &enemy_sprite::MAGEIDLE,
+        }
+    }
+}
+
+pub struct Enemy {
     object: Object,
-    tag: &'a Tag,
+    tag: &'static Tag,
+    x: i32,
+    y: i32,
     accumulator: usize,
     anim_frame: usize,
 }
 
-impl<'a> Enemy<'a> {
-    pub fn new(object: Object, tag: &'a Tag) -> Self {
+impl Enemy {
+    pub fn new(kind: EnemyKind, slot: usize) -> Self {
+        let tag = kind.idle_tag();
+        let (x, priority) = SLOTS[slot];
+
+        let mut object = Object::new(tag.sprite(0));
+        object.set_pos((x, ENEMY_Y));
+        object.set_priority(priority);
+
         Self {
             object,
             tag,
+            x,
+            y: ENEMY_Y,
             accumulator: 0,
             anim_frame: 0,
         }
     }
 
+    fn move_to_slot(&mut self, slot: usize) {
+        self.object.set_priority(SLOTS[slot].1);
+    }
+
+    fn step_towards(&mut self, slot: usize) {
+        let target = SLOTS[slot].0;
+        self.x += (target - self.x).clamp(-2, 2);
+        self.object.set_pos((self.x, self.y));
+    }
+
     pub fn update(&mut self) {
         self.object.set_sprite(self.tag.sprite(self.anim_frame));
 
@@ -45,27 +139,79 @@ impl<'a> Enemy<'a> {
     }
 }
 
-pub fn setup_enemies<'a>() -> [Enemy<'a>; 4] {
-    let mut enemy0 = Object::new(enemy_sprite::BIGROCKIDLE.sprite(0));
-    enemy0.set_pos((121 + 60, 54));
-    enemy0.set_priority(Priority::P3);
-
-    let mut enemy1 = Object::new(enemy_sprite::LERCIOIDLE.sprite(0));
-    enemy1.set_pos((121 + 40, 54));
-    enemy1.set_priority(Priority::P2);
-
-    let mut enemy2 = Object::new(enemy_sprite::GOBLINIDLE.sprite(0));
-    enemy2.set_pos((121 + 20, 54));
-    enemy2.set_priority(Priority::P1);
-
-    let mut enemy3 = Object::new(enemy_sprite::MAGEIDLE.sprite(0));
-    enemy3.set_pos((121, 54));
-    enemy3.set_priority(Priority::P0);
-
-    [
-        Enemy::new(enemy0, &enemy_sprite::BIGROCKIDLE),
-        Enemy::new(enemy1, &enemy_sprite::LERCIOIDLE),
-        Enemy::new(enemy2, &enemy_sprite::GOBLINIDLE),
-        Enemy::new(enemy3, &enemy_sprite::MAGEIDLE),
-    ]
+struct Defeated {
+    enemy: Enemy,
+    frames: usize,
+}
+
+pub struct EnemyQueue {
+    enemies: [Enemy; 4],
+    defeated: Option<Defeated>,
+}
+
+impl EnemyQueue {
+    pub fn new(scenario: &Scenario) -> Self {
+        Self {
+            enemies: core::array::from_fn(|slot| Enemy::new(scenario.enemies[slot], slot)),
+            defeated: None,
+        }
+    }
+
+    pub fn reset(&mut self, scenario: &Scenario) {
+        *self = Self::new(scenario);
+    }
+
+    // Mirrors `Scenario::next`: the front enemy is knocked out, everyone
+    // else steps forward and a new enemy joins at the back
+    pub fn advance(&mut self, scenario: &Scenario) {
+        self.enemies.rotate_right(1);
+
+        let spawned = Enemy::new(scenario.enemies[0], 0);
+        let mut defeated = core::mem::replace(&mut self.enemies[0], spawned);
+        defeated.object.set_priority(Priority::P0);
+
+        self.defeated = Some(Defeated {
+            enemy: defeated,
+            frames: 0,
+        });
+
+        for (slot, enemy) in self.enemies.iter_mut().enumerate() {
+            enemy.move_to_slot(slot);
+        }
+    }
+
+    pub fn update(&mut self) {
+        for (slot, enemy) in self.enemies.iter_mut().enumerate() {
+            enemy.step_towards(slot);
+        }
+
+        self.enemies[FRONT].update();
+
+        if let Some(defeated) = self.defeated.as_mut() {
+            defeated.frames += 1;
+            defeated.enemy.x += 3;
+            defeated.enemy.y -= 2;
+            defeated
+                .enemy
+                .object
+                .set_pos((defeated.enemy.x, defeated.enemy.y));
+
+            if defeated.frames > DEFEAT_FRAMES {
+                self.defeated = None;
+            }
+        }
+    }
+
+    pub fn draw(&self, frame: &mut GraphicsFrame<'_>) {
+        // Blink the defeated enemy out while it flies off
+        if let Some(defeated) = &self.defeated
+            && defeated.frames % 4 < 2
+        {
+            defeated.enemy.draw(frame);
+        }
+
+        for enemy in self.enemies.iter().rev() {
+            enemy.draw(frame);
+        }
+    }
 }
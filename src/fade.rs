@@ -0,0 +1,116 @@
+use agb::{
+    display::{GraphicsFrame, blend::Layer, tiled::BackgroundId},
+    fixnum::Num,
+};
+
+pub const DEFAULT_FADE_FRAMES: usize = 16;
+
+// The blend registers take the fade strength in sixteenths
+const FULL_STRENGTH: usize = 16;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FadeStyle {
+    Black,
+    White,
+    Cross,
+}
+
+// A transition between two scenes. Black and white fade the old scene out,
+// enter the new one while the screen is solid, then fade it back in.
+//
+// A cross-fade enters the new scene straight away and shows both, blending
+// the old scene's backgrounds over the new one's. The hardware only blends a
+// pixel with the one directly beneath it, so the old scene has to be drawn
+// above the new one; screens with a single background use the highest
+// priority for that. Sprites are not blended.
+#[derive(Clone, Copy)]
+pub struct Fade {
+    pub style: FadeStyle,
+    pub frames: usize,
+}
+
+impl Fade {
+    pub const fn black() -> Self {
+        Self {
+            style: FadeStyle::Black,
+            frames: DEFAULT_FADE_FRAMES,
+        }
+    }
+
+    pub const fn white() -> Self {
+        Self {
+            style: FadeStyle::White,
+            frames: DEFAULT_FADE_FRAMES,
+        }
+    }
+
+    pub const fn cross() -> Self {
+        Self {
+            style: FadeStyle::Cross,
+            frames: DEFAULT_FADE_FRAMES,
+        }
+    }
+
+    // How far through the fade `progress` out of `self.frames` is, in
+    // sixteenths
+    fn strength(&self, progress: usize) -> usize {
+        (progress.min(self.frames) * FULL_STRENGTH)
+            .checked_div(self.frames)
+            .unwrap_or(FULL_STRENGTH)
+    }
+
+    // Blends everything shown this frame towards the fade colour, where
+    // `progress` out of `self.frames` is fully faded
+    pub fn apply(
+        &self,
+        frame: &mut GraphicsFrame<'_>,
+        backgrounds: &[BackgroundId],
+        progress: usize,
+    ) {
+        let strength = self.strength(progress);
+
+        if strength == 0 {
+            return;
+        }
+
+        let amount = Num::<u8, 4>::from_raw(strength as u8);
+
+        let blend = frame.blend();
+        let mut effect = match self.style {
+            FadeStyle::Black => blend.darken(amount),
+            FadeStyle::White => blend.brighten(amount),
+            FadeStyle::Cross => return,
+        };
+
+        for &background in backgrounds {
+            effect.enable_background(background);
+        }
+        effect.enable_object().enable_backdrop();
+    }
+
+    // Blends the old scene's backgrounds over the new scene's, where
+    // `progress` out of `self.frames` shows only the new scene
+    pub fn cross(
+        &self,
+        frame: &mut GraphicsFrame<'_>,
+        previous: &[BackgroundId],
+        backgrounds: &[BackgroundId],
+        progress: usize,
+    ) {
+        let strength = self.strength(progress);
+
+        let blend = frame.blend();
+        let mut effect = blend.alpha(
+            Num::<u8, 4>::from_raw((FULL_STRENGTH - strength) as u8),
+            Num::<u8, 4>::from_raw(strength as u8),
+        );
+
+        for &background in previous {
+            effect.enable_background(Layer::Top, background);
+        }
+        for &background in backgrounds {
+            effect.enable_background(Layer::Bottom, background);
+        }
+        effect.enable_backdrop(Layer::Bottom);
+    }
+}
@@ -0,0 +1,435 @@
+use agb::{
+    display::{
+        GraphicsFrame, Priority,
+        font::AlignmentKind,
+        tiled::{BackgroundId, RegularBackground, RegularBackgroundSize, TileFormat},
+    },
+    fixnum::vec2,
+    input::Button,
+};
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+
+use crate::{
+    background,
+    binding::{ActionType, button_names, held_buttons},
+    buttons::ButtonHints,
+    countdown::Direction,
+    enemy::{EnemyQueue, Requirement},
+    fade::Fade,
+    game_over::GameOverScene,
+    game_state::{GameEvent, GameState},
+    hearts::draw_hearts,
+    label::Label,
+    pause::{PauseChoice, PauseMenu},
+    player::Player,
+    popup::Popups,
+    replay::{Replay, ReplayFrame, just_pressed},
+    rules::TimerMode,
+    scenario::ScenarioView,
+    scene::{Context, Scene, Transition},
+    sfx_manager::{CROSSFADE_FRAMES, SoundEffect, Track},
+    title_screen::TitleScene,
+};
+
+// How the next run is seeded: a fresh seed, or a recording to play back
+pub enum NextRun {
+    Seed(u32),
+    Replay(Replay),
+}
+
+pub struct GameScene {
+    player: Player,
+    hints: ButtonHints,
+    game_bg: RegularBackground,
+    game: GameState,
+    scenario_view: ScenarioView,
+    enemies: EnemyQueue,
+    popups: Popups,
+    replay: Option<Replay>,
+    playback_frame: usize,
+    recording: Option<Replay>,
+    previous: Button,
+    // Buttons still held from closing the pause menu, ignored until they are
+    // released so the press doesn't carry into the run
+    held_over: Button,
+    pause: Option<PauseMenu>,
+    // The last whole second ticked during the final countdown
+    ticked_second: usize,
+}
+
+// Seconds left at which the countdown starts ticking
+const TICK_SECONDS: usize = 3;
+
+// Streaks at which the drums and then the lead join the music
+const DRUMS_STREAK: usize = 5;
+const LEAD_STREAK: usize = 15;
+
+// How many layers of the game theme play on top of the base
+fn music_intensity(game: &GameState) -> usize {
+    let countdown = game.countdown();
+    if countdown.direction() == Direction::Down && countdown.seconds_left() <= TICK_SECONDS {
+        return 2;
+    }
+
+    match game.streak() {
+        streak if streak >= LEAD_STREAK => 2,
+        streak if streak >= DRUMS_STREAK => 1,
+        _ => 0,
+    }
+}
+
+fn action_sound(action: ActionType) -> SoundEffect {
+    match action {
+        ActionType::Attack => SoundEffect::Slash,
+        ActionType::Shield => SoundEffect::Clang,
+        ActionType::Jump => SoundEffect::Whoosh,
+    }
+}
+
+impl GameScene {
+    // Sets up a run from the seed or the replay, with the rules of the
+    // selected mode unless a replay brings its own
+    pub fn new(next: NextRun, ctx: &Context<'_>) -> Self {
+        let mut game_bg = RegularBackground::new(
+            Priority::P3,
+            RegularBackgroundSize::Background32x32,
+            TileFormat::FourBpp,
+        );
+
+        game_bg.fill_with(&background::GAME);
+
+        let (seed, replay) = match next {
+            NextRun::Seed(seed) => (seed, None),
+            NextRun::Replay(replay) => (replay.seed(), Some(replay)),
+        };
+
+        let (rules, bindings) = match replay.as_ref() {
+            Some(replay) => (*replay.rules(), replay.bindings().clone()),
+            None => (ctx.mode.rules(), ctx.save_game.bindings.clone()),
+        };
+
+        let mut game = GameState::new(rules, seed);
+        game.set_bindings(bindings.clone());
+
+        let hints = ButtonHints::new(&bindings);
+
+        let mut scenario_view = ScenarioView::new();
+        scenario_view.set_transition_frames(rules.transition_frames);
+        scenario_view.update(game.scenario());
+        let enemies = EnemyQueue::new(game.scenario());
+
+        Self {
+            player: Player::new(),
+            hints,
+            game_bg,
+            game,
+            scenario_view,
+            enemies,
+            popups: Popups::new(),
+            replay,
+            playback_frame: 0,
+            recording: Some(Replay::new(seed, rules, bindings)),
+            previous: Button::empty(),
+            held_over: Button::empty(),
+            pause: None,
+            ticked_second: 0,
+        }
+    }
+
+    // Starts over with a fresh seed and the selected mode, even when
+    // restarting a replay
+    fn restart(&mut self, ctx: &mut Context<'_>) {
+        let seed = self.game.next_seed();
+        let rules = ctx.mode.rules();
+        let bindings = ctx.save_game.bindings.clone();
+
+        self.replay = None;
+        self.recording = Some(Replay::new(seed, rules, bindings.clone()));
+        self.playback_frame = 0;
+        self.previous = Button::empty();
+        self.pause = None;
+        self.ticked_second = 0;
+
+        self.game.set_rules(rules);
+        self.hints = ButtonHints::new(&bindings);
+        self.game.set_bindings(bindings);
+        self.game.reset(seed);
+        self.player.reset();
+        ctx.clock.reset();
+
+        self.scenario_view
+            .set_transition_frames(rules.transition_frames);
+        self.scenario_view.update(self.game.scenario());
+        self.enemies.reset(self.game.scenario());
+        self.popups.clear();
+    }
+
+    fn next_frame(&mut self, ctx: &mut Context<'_>) -> ReplayFrame {
+        let elapsed = ctx.clock.elapsed();
+
+        match self.replay.as_ref() {
+            Some(replay) => {
+                let frame = replay.frame(self.playback_frame).unwrap_or(ReplayFrame {
+                    buttons: Button::empty(),
+                    elapsed,
+                });
+                self.playback_frame += 1;
+                frame
+            }
+            None => {
+                let held = held_buttons(&ctx.input);
+                self.held_over &= held;
+
+                ReplayFrame {
+                    buttons: held.difference(self.held_over),
+                    elapsed,
+                }
+            }
+        }
+    }
+
+    fn tick_countdown(&mut self, ctx: &mut Context<'_>) {
+        let countdown = self.game.countdown();
+        if countdown.direction() != Direction::Down {
+            return;
+        }
+
+        let second = countdown.seconds_left();
+        if second <= TICK_SECONDS && second > 0 && second != self.ticked_second {
+            ctx.sfx.play_effect(SoundEffect::Tick).ok();
+        }
+        self.ticked_second = second;
+    }
+
+    fn game_over(&mut self, ctx: &mut Context<'_>) -> GameOverScene {
+        let summary = self.game.summary();
+        let mode = ctx.mode;
+
+        let table_score = mode.table_score(&summary).filter(|_| self.replay.is_none());
+        let record = table_score.and_then(|score| {
+            let rank = ctx.save_game.high_scores(mode).rank(score)?;
+            Some((rank, score))
+        });
+
+        GameOverScene::new(
+            &summary,
+            mode,
+            record,
+            self.recording.take(),
+            self.game.next_seed(),
+        )
+    }
+}
+
+impl Scene for GameScene {
+    fn enter(&mut self, ctx: &mut Context<'_>) {
+        // The run goes on in silence if the mixer is out of channels
+        ctx.sfx.set_intensity(0);
+        ctx.sfx.crossfade_to(Track::Game, CROSSFADE_FRAMES).ok();
+
+        ctx.clock.reset();
+    }
+
+    fn update(&mut self, ctx: &mut Context<'_>) -> Transition {
+        if let Some(menu) = self.pause.as_mut() {
+            match menu.update(&ctx.input) {
+                Some(PauseChoice::Resume) => {
+                    self.pause = None;
+                    self.held_over = held_buttons(&ctx.input);
+                    ctx.clock.reset();
+                    ctx.sfx.duck(false);
+                }
+                Some(PauseChoice::Restart) => {
+                    ctx.sfx.duck(false);
+                    self.restart(ctx);
+                    self.held_over = held_buttons(&ctx.input);
+                }
+                Some(PauseChoice::Quit) => {
+                    return Transition::Fade(Box::new(TitleScene::new()), Fade::black());
+                }
+                None => {}
+            }
+
+            return Transition::Stay;
+        }
+
+        if ctx.input.is_just_pressed(Button::START) {
+            self.pause = Some(PauseMenu::new());
+            ctx.sfx.duck(true);
+            return Transition::Stay;
+        }
+
+        let input_frame = self.next_frame(ctx);
+
+        if let Some(recording) = self.recording.as_mut()
+            && !recording.record(input_frame.buttons, input_frame.elapsed)
+        {
+            self.recording = None;
+        }
+
+        let pressed = just_pressed(input_frame.buttons, self.previous);
+        self.previous = input_frame.buttons;
+
+        for event in self.game.step(pressed, input_frame.elapsed) {
+            match event {
+                GameEvent::Action(action) => {
+                    self.player.perform_action(action);
+                    ctx.sfx.play_effect(action_sound(action)).ok();
+                }
+                GameEvent::Defeated { points } => {
+                    self.scenario_view.transition(self.game.scenario());
+                    self.enemies.advance(self.game.scenario());
+                    // A line above the next enemy's hits label
+                    self.popups
+                        .spawn(format!("+{points}"), vec2(121 + 4, 54 - 12 - 14));
+                }
+                GameEvent::Hurt { .. } => {
+                    self.player.hurt();
+                    ctx.sfx.play_effect(SoundEffect::Buzz).ok();
+                }
+                GameEvent::Died => {
+                    self.player.kill();
+                    ctx.sfx.play_effect(SoundEffect::Buzz).ok();
+                }
+                GameEvent::Mistake { .. } => {
+                    ctx.sfx.play_effect(SoundEffect::Buzz).ok();
+                }
+                GameEvent::TimeBonus { .. }
+                | GameEvent::StreakBroken
+                | GameEvent::TimeUp
+                | GameEvent::Completed
+                | GameEvent::Finished => {}
+            }
+        }
+
+        if self.game.is_finished() {
+            // Fade out to black after dying and to white when time runs out
+            let fade = if self.game.is_dead() {
+                Fade::black()
+            } else {
+                Fade::white()
+            };
+            return Transition::Fade(Box::new(self.game_over(ctx)), fade);
+        }
+
+        self.tick_countdown(ctx);
+        ctx.sfx.set_intensity(music_intensity(&self.game));
+
+        self.player.set_blinking(self.game.is_invulnerable());
+        self.player.update();
+        self.enemies.update();
+        self.scenario_view.animate();
+        self.popups.update();
+
+        Transition::Stay
+    }
+
+    fn draw(&mut self, frame: &mut GraphicsFrame<'_>, backgrounds: &mut Vec<BackgroundId>) {
+        let game = &self.game;
+
+        let mut score_label = Label::new(
+            &format!("Score: {0}", game.score()),
+            vec2(8, 3),
+            AlignmentKind::Left,
+            18,
+            80,
+        );
+        let mut combo_label = (game.streak() > 1).then(|| {
+            Label::new(
+                &format!("Combo {0} x{1}", game.streak(), game.multiplier()),
+                vec2(8, 14),
+                AlignmentKind::Left,
+                18,
+                80,
+            )
+        });
+
+        let millis = game.countdown().millis();
+        let mut time_label = (game.rules().timer != TimerMode::Off).then(|| {
+            Label::new(
+                &format!("Time: {0}.{1}", millis / 1000, millis % 1000 / 100),
+                vec2(0, 3),
+                AlignmentKind::Right,
+                16,
+                232,
+            )
+        });
+
+        let mut kills_label = game.rules().target_kills.map(|target| {
+            Label::new(
+                &format!("Kills: {0}/{1}", game.kills(), target),
+                vec2(0, 14),
+                AlignmentKind::Right,
+                16,
+                232,
+            )
+        });
+
+        // Sequences show which action comes next, since it can differ from
+        // the scenario's own
+        let hits_text = match game.scenario().current_enemy().def().requirement {
+            Requirement::Sequence(_) => {
+                let action = game.required_action();
+                let buttons = game.bindings().buttons_for(action);
+                Some(format!("{0} {1}", action.name(), button_names(buttons)))
+            }
+            Requirement::Hits(hits) if hits > 1 => Some(format!("x{0}", game.hits_left())),
+            Requirement::Hits(_) => None,
+        };
+        let mut hits_label = hits_text
+            .map(|text| Label::new(&text, vec2(121 + 4, 54 - 12), AlignmentKind::Left, 18, 80));
+
+        if let Some(menu) = self.pause.as_mut() {
+            menu.draw(frame);
+        }
+
+        self.player.draw(frame);
+
+        self.enemies.draw(frame);
+
+        self.hints.draw(frame);
+
+        backgrounds.push(self.game_bg.show(frame));
+
+        self.scenario_view.draw(frame, backgrounds);
+
+        score_label.draw(frame);
+
+        if let Some(time_label) = time_label.as_mut() {
+            time_label.draw(frame);
+        }
+
+        if let Some(kills_label) = kills_label.as_mut() {
+            kills_label.draw(frame);
+        }
+
+        if let Some(hits_label) = hits_label.as_mut() {
+            hits_label.draw(frame);
+        }
+
+        if let Some(combo_label) = combo_label.as_mut() {
+            combo_label.draw(frame);
+        }
+
+        self.popups.draw(frame);
+
+        if game.rules().lives > 1 {
+            draw_hearts(frame, game.lives(), game.rules().lives);
+        }
+
+        if self.replay.is_some() {
+            let mut replay_label = Label::new(
+                &String::from("Replay"),
+                vec2(8, 144),
+                AlignmentKind::Left,
+                18,
+                80,
+            );
+            replay_label.draw(frame);
+        }
+    }
+
+    fn exit(&mut self, ctx: &mut Context<'_>) {
+        ctx.sfx.duck(false);
+    }
+}
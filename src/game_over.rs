@@ -1,57 +1,223 @@
 use agb::{
     display::{
-        Graphics, Priority,
+        GraphicsFrame, Priority,
         font::AlignmentKind,
-        tiled::{RegularBackground, RegularBackgroundSize, TileFormat, VRAM_MANAGER},
+        tiled::{BackgroundId, RegularBackground, RegularBackgroundSize, TileFormat},
     },
     fixnum::vec2,
-    include_background_gfx,
     input::{Button, ButtonController},
 };
-use alloc::format;
+use alloc::{boxed::Box, format, string::String, vec::Vec};
 
-use crate::{label::Label, sfx_manager::Sfx};
-
-include_background_gfx!(
-    mod game_over_screen,
-    GAME_OVER => deduplicate "gfx/game-over.png",
-);
+use crate::{
+    background,
+    fade::Fade,
+    game::{GameScene, NextRun},
+    game_state::RunSummary,
+    high_score::NAME_LEN,
+    label::Label,
+    mode::{GameMode, ScoreOrder},
+    replay::Replay,
+    scene::{Context, Scene, Transition},
+    sfx_manager::SoundEffect,
+};
 
-pub fn show_game_over_screen(score: usize, gfx: &mut Graphics, sfx: &mut Sfx) {
+fn game_over_background() -> RegularBackground {
     let mut map = RegularBackground::new(
-        Priority::P3,
+        Priority::P0,
         RegularBackgroundSize::Background32x32,
         TileFormat::FourBpp,
     );
 
-    sfx.stop();
+    map.fill_with(&background::GAME_OVER);
+    map
+}
 
-    let mut input = ButtonController::new();
+pub struct GameOverScene {
+    map: RegularBackground,
+    label: Label,
+    // The rank and table score, if the run made the high-score table
+    record: Option<(usize, u32)>,
+    recording: Option<Replay>,
+    next_seed: u32,
+}
 
-    map.fill_with(&game_over_screen::GAME_OVER);
+impl GameOverScene {
+    pub fn new(
+        summary: &RunSummary,
+        mode: GameMode,
+        record: Option<(usize, u32)>,
+        recording: Option<Replay>,
+        next_seed: u32,
+    ) -> Self {
+        let result = match mode.score_order() {
+            ScoreOrder::Higher => format!("Final score: {0}", summary.score),
+            ScoreOrder::Lower if summary.completed => {
+                format!("Time: {0}", mode.format_score(summary.millis))
+            }
+            ScoreOrder::Lower => String::from("Not finished"),
+        };
 
-    VRAM_MANAGER.set_background_palettes(game_over_screen::PALETTES);
+        let rank_text = match record {
+            Some((0, _)) => String::from("\nNew record!"),
+            Some((rank, _)) => format!("\nRank {0}", rank + 1),
+            None => String::new(),
+        };
+
+        let label = Label::new(
+            &format!(
+                "Game Over\n{0}\n{result}{rank_text}\nBest streak: {1}\nSeed: {2:08X}",
+                mode.name(),
+                summary.best_streak,
+                summary.seed
+            ),
+            vec2(120 - 40, 80 - 16),
+            AlignmentKind::Centre,
+            18,
+            80,
+        );
 
-    loop {
-        sfx.frame();
-        input.update();
+        Self {
+            map: game_over_background(),
+            label,
+            record,
+            recording,
+            next_seed,
+        }
+    }
 
+    // START plays a fresh run and SELECT watches the one that just ended
+    fn choice(&mut self, input: &ButtonController) -> Option<NextRun> {
         if input.is_just_pressed(Button::START) {
-            break;
+            return Some(NextRun::Seed(self.next_seed));
+        }
+
+        if input.is_just_pressed(Button::SELECT) {
+            return Some(match self.recording.take() {
+                Some(recording) => NextRun::Replay(recording),
+                None => NextRun::Seed(self.next_seed),
+            });
         }
 
-        let mut frame = gfx.frame();
-        map.show(&mut frame);
+        None
+    }
+}
+
+impl Scene for GameOverScene {
+    fn enter(&mut self, ctx: &mut Context<'_>) {
+        // The game theme plays on under the results
+        ctx.sfx.duck(true);
+
+        if let Some((0, _)) = self.record {
+            ctx.sfx.play_effect(SoundEffect::Jingle).ok();
+        }
+    }
+
+    fn update(&mut self, ctx: &mut Context<'_>) -> Transition {
+        let Some(next) = self.choice(&ctx.input) else {
+            return Transition::Stay;
+        };
 
-        let mut score_label = Label::new(
-            &format!("Game Over\nFinal score: {score}"),
+        match self.record {
+            Some((_, score)) => Transition::Replace(Box::new(NameEntryScene::new(score, next))),
+            None => Transition::Fade(Box::new(GameScene::new(next, ctx)), Fade::cross()),
+        }
+    }
+
+    fn draw(&mut self, frame: &mut GraphicsFrame<'_>, backgrounds: &mut Vec<BackgroundId>) {
+        backgrounds.push(self.map.show(frame));
+        self.label.draw(frame);
+    }
+
+    fn exit(&mut self, ctx: &mut Context<'_>) {
+        ctx.sfx.duck(false);
+    }
+}
+
+const LETTERS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 ";
+
+pub struct NameEntryScene {
+    map: RegularBackground,
+    name: String,
+    letter: usize,
+    score: u32,
+    next: Option<NextRun>,
+}
+
+impl NameEntryScene {
+    pub fn new(score: u32, next: NextRun) -> Self {
+        Self {
+            map: game_over_background(),
+            name: String::new(),
+            letter: 0,
+            score,
+            next: Some(next),
+        }
+    }
+}
+
+impl Scene for NameEntryScene {
+    fn enter(&mut self, ctx: &mut Context<'_>) {
+        ctx.sfx.duck(true);
+    }
+
+    fn update(&mut self, ctx: &mut Context<'_>) -> Transition {
+        let input = &ctx.input;
+
+        if input.is_just_pressed(Button::UP) {
+            self.letter = (self.letter + LETTERS.len() - 1) % LETTERS.len();
+        }
+
+        if input.is_just_pressed(Button::DOWN) {
+            self.letter = (self.letter + 1) % LETTERS.len();
+        }
+
+        if input.is_just_pressed(Button::A) && self.name.len() < NAME_LEN {
+            self.name.push(LETTERS[self.letter] as char);
+        }
+
+        if input.is_just_pressed(Button::B) {
+            self.name.pop();
+        }
+
+        if input.is_just_pressed(Button::START)
+            && !self.name.trim().is_empty()
+            && let Some(next) = self.next.take()
+        {
+            ctx.save_game
+                .high_scores_mut(ctx.mode)
+                .insert(self.score, &self.name);
+            ctx.storage.store(&ctx.save_game);
+
+            return Transition::Fade(Box::new(GameScene::new(next, ctx)), Fade::cross());
+        }
+
+        Transition::Stay
+    }
+
+    fn draw(&mut self, frame: &mut GraphicsFrame<'_>, backgrounds: &mut Vec<BackgroundId>) {
+        backgrounds.push(self.map.show(frame));
+
+        let cursor = if self.name.len() < NAME_LEN {
+            LETTERS[self.letter] as char
+        } else {
+            ' '
+        };
+
+        let mut name_label = Label::new(
+            &format!(
+                "New high score!\nName: {0}[{cursor}]\nA: add  B: delete",
+                self.name
+            ),
             vec2(120 - 40, 80 - 16),
             AlignmentKind::Centre,
             18,
             80,
         );
-        score_label.draw(&mut frame);
+        name_label.draw(frame);
+    }
 
-        frame.commit();
+    fn exit(&mut self, ctx: &mut Context<'_>) {
+        ctx.sfx.duck(false);
     }
 }
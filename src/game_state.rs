@@ -0,0 +1,425 @@
+use agb::input::Button;
+use alloc::vec::Vec;
+
+use crate::binding::{ActionType, Bindings};
+use crate::countdown::{Countdown, Direction, millis_to_ticks};
+use crate::enemy::Requirement;
+use crate::rules::Rules;
+use crate::scenario::Scenario;
+
+const DEATH_FRAMES: usize = 50;
+
+#[derive(Clone, Copy)]
+pub struct RunSummary {
+    pub score: usize,
+    pub seed: u32,
+    pub best_streak: usize,
+    pub kills: usize,
+    pub millis: u32,
+    pub completed: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GameEvent {
+    Action(ActionType),
+    Defeated { points: usize },
+    TimeBonus { millis: u32 },
+    Mistake { penalty: u32 },
+    Hurt { lives: usize },
+    StreakBroken,
+    Died,
+    TimeUp,
+    Completed,
+    Finished,
+}
+
+pub struct GameState {
+    rules: Rules,
+    scenario: Scenario,
+    bindings: Bindings,
+    countdown: Countdown,
+    score: usize,
+    kills: usize,
+    hits_taken: usize,
+    streak: usize,
+    best_streak: usize,
+    since_action: u32,
+    lives: usize,
+    invulnerable: u32,
+    dead: bool,
+    death_counter: usize,
+    completed: bool,
+    finished: bool,
+}
+
+impl GameState {
+    pub fn new(rules: Rules, seed: u32) -> Self {
+        let scenario = Scenario::new(seed);
+
+        let mut countdown = rules.countdown();
+        countdown.reset();
+
+        Self {
+            rules,
+            scenario,
+            bindings: Bindings::new(),
+            countdown,
+            score: 0,
+            kills: 0,
+            hits_taken: 0,
+            streak: 0,
+            best_streak: 0,
+            since_action: 0,
+            lives: rules.lives,
+            invulnerable: 0,
+            dead: false,
+            death_counter: 0,
+            completed: false,
+            finished: false,
+        }
+    }
+
+    pub fn reset(&mut self, seed: u32) {
+        self.scenario.reseed(seed);
+        self.countdown.reset();
+        self.score = 0;
+        self.kills = 0;
+        self.hits_taken = 0;
+        self.streak = 0;
+        self.best_streak = 0;
+        self.since_action = 0;
+        self.lives = self.rules.lives;
+        self.invulnerable = 0;
+        self.dead = false;
+        self.death_counter = 0;
+        self.completed = false;
+        self.finished = false;
+    }
+
+    pub const fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    // Takes effect on the next reset
+    pub fn set_rules(&mut self, rules: Rules) {
+        self.rules = rules;
+        self.countdown = rules.countdown();
+    }
+
+    pub const fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    pub fn set_bindings(&mut self, bindings: Bindings) {
+        self.bindings = bindings;
+    }
+
+    pub const fn scenario(&self) -> &Scenario {
+        &self.scenario
+    }
+
+    pub const fn seed(&self) -> u32 {
+        self.scenario.seed()
+    }
+
+    pub fn next_seed(&mut self) -> u32 {
+        self.scenario.next_seed()
+    }
+
+    pub const fn score(&self) -> usize {
+        self.score
+    }
+
+    pub const fn countdown(&self) -> &Countdown {
+        &self.countdown
+    }
+
+    pub const fn kills(&self) -> usize {
+        self.kills
+    }
+
+    pub const fn lives(&self) -> usize {
+        self.lives
+    }
+
+    pub const fn is_invulnerable(&self) -> bool {
+        self.invulnerable > 0
+    }
+
+    pub const fn streak(&self) -> usize {
+        self.streak
+    }
+
+    pub const fn multiplier(&self) -> usize {
+        self.rules.multiplier(self.streak)
+    }
+
+    pub const fn summary(&self) -> RunSummary {
+        RunSummary {
+            score: self.score,
+            seed: self.scenario.seed(),
+            best_streak: self.best_streak,
+            kills: self.kills,
+            millis: self.countdown.millis(),
+            completed: self.completed,
+        }
+    }
+
+    pub fn hits_left(&self) -> usize {
+        self.scenario.current_enemy().def().hits() - self.hits_taken
+    }
+
+    pub fn required_action(&self) -> ActionType {
+        match self.scenario.current_enemy().def().requirement {
+            Requirement::Hits(_) => self.scenario.current().def().action,
+            Requirement::Sequence(actions) => actions[self.hits_taken],
+        }
+    }
+
+    pub const fn is_dead(&self) -> bool {
+        self.dead
+    }
+
+    pub const fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    pub fn step(&mut self, pressed_buttons: Button, elapsed: u32) -> Vec<GameEvent> {
+        let mut events = Vec::new();
+
+        if self.finished {
+            return events;
+        }
+
+        if self.dead {
+            if self.death_counter > DEATH_FRAMES {
+                self.finish(&mut events);
+            }
+            self.death_counter += 1;
+            return events;
+        }
+
+        self.countdown.update(elapsed);
+
+        if self.countdown.is_finished() {
+            events.push(GameEvent::TimeUp);
+            self.finish(&mut events);
+            return events;
+        }
+
+        self.invulnerable = self.invulnerable.saturating_sub(elapsed);
+
+        self.since_action = self.since_action.saturating_add(elapsed);
+        if self.since_action > millis_to_ticks(self.rules.combo_window_ms) {
+            self.break_streak(&mut events);
+        }
+
+        for binding in self.bindings.iter() {
+            if !pressed_buttons.intersects(binding.buttons) {
+                continue;
+            }
+
+            if self.required_action() != binding.action {
+                if self.invulnerable > 0 {
+                    continue;
+                }
+
+                self.hits_taken = 0;
+                self.break_streak(&mut events);
+
+                if let Some(penalty) = self.rules.mistake_penalty_ms {
+                    match self.countdown.direction() {
+                        Direction::Down => self.countdown.subtract_time(penalty),
+                        Direction::Up => self.countdown.add_time(penalty),
+                    }
+                    events.push(GameEvent::Mistake { penalty });
+                    continue;
+                }
+
+                self.lives = self.lives.saturating_sub(1);
+
+                if self.lives == 0 {
+                    self.dead = true;
+                    events.push(GameEvent::Died);
+                    break;
+                }
+
+                self.invulnerable = millis_to_ticks(self.rules.invulnerability_ms);
+                events.push(GameEvent::Hurt { lives: self.lives });
+                continue;
+            }
+
+            events.push(GameEvent::Action(binding.action));
+            self.hits_taken += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+            self.since_action = 0;
+
+            let millis = self.rules.time_bonus(self.score);
+            if millis > 0 {
+                self.countdown.add_time(millis);
+                events.push(GameEvent::TimeBonus { millis });
+            }
+
+            if self.hits_left() == 0 {
+                let points = self.scenario.current_enemy().def().points * self.multiplier();
+                self.scenario.next();
+                self.score += points;
+                self.kills += 1;
+                self.hits_taken = 0;
+                events.push(GameEvent::Defeated { points });
+
+                if self.rules.target_kills == Some(self.kills) {
+                    self.completed = true;
+                    events.push(GameEvent::Completed);
+                    self.finish(&mut events);
+                    break;
+                }
+            }
+        }
+
+        events
+    }
+
+    fn break_streak(&mut self, events: &mut Vec<GameEvent>) {
+        if self.streak > 0 {
+            self.streak = 0;
+            events.push(GameEvent::StreakBroken);
+        }
+    }
+
+    fn finish(&mut self, events: &mut Vec<GameEvent>) {
+        self.finished = true;
+        events.push(GameEvent::Finished);
+    }
+}
+
+// Helpers shared by the tests of the game rules and of replays
+#[cfg(test)]
+pub(crate) mod test_helpers {
+    use super::*;
+
+    // The buttons of the first scenario bound to `action`
+    pub fn buttons_for(game: &GameState, action: ActionType) -> Button {
+        game.bindings()
+            .iter()
+            .find(|binding| binding.action == action)
+            .unwrap()
+            .buttons
+    }
+
+    // Any action the current enemy doesn't need
+    pub fn wrong_action(game: &GameState) -> ActionType {
+        [ActionType::Attack, ActionType::Shield, ActionType::Jump]
+            .into_iter()
+            .find(|&action| action != game.required_action())
+            .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_helpers::{buttons_for, wrong_action};
+    use super::*;
+    use crate::countdown::TICKS_PER_SECOND;
+    use crate::mode::GameMode;
+
+    const SEED: u32 = 0x1234_5678;
+
+    #[test_case]
+    fn correct_action_defeats_enemies(_gba: &mut agb::Gba) {
+        let mut game = GameState::new(Rules::DEFAULT, SEED);
+
+        let action = game.required_action();
+        let hits = game.hits_left();
+        let events = game.step(buttons_for(&game, action), 1);
+
+        assert_eq!(events.first(), Some(&GameEvent::Action(action)));
+        assert_eq!(game.streak(), 1);
+        assert!(!game.is_dead());
+
+        for _ in 1..hits {
+            let action = game.required_action();
+            game.step(buttons_for(&game, action), 1);
+        }
+
+        assert_eq!(game.kills(), 1);
+        assert!(game.score() > 0);
+    }
+
+    #[test_case]
+    fn every_correct_action_adds_time(_gba: &mut agb::Gba) {
+        let mut game = GameState::new(Rules::DEFAULT, SEED);
+        let bonus = Rules::DEFAULT.time_bonus(0);
+
+        let action = game.required_action();
+        let events = game.step(buttons_for(&game, action), 1);
+
+        assert_eq!(events[1], GameEvent::TimeBonus { millis: bonus });
+        assert!(game.countdown().millis() > Rules::DEFAULT.seconds as u32 * 1000);
+    }
+
+    #[test_case]
+    fn wrong_action_costs_time_in_survival(_gba: &mut agb::Gba) {
+        let rules = GameMode::Survival.rules();
+        let penalty = rules.mistake_penalty_ms.unwrap();
+        let mut game = GameState::new(rules, SEED);
+
+        let action = wrong_action(&game);
+        let events = game.step(buttons_for(&game, action), 1);
+
+        assert_eq!(events, [GameEvent::Mistake { penalty }]);
+        assert!(!game.is_dead());
+        assert!(game.countdown().millis() < rules.seconds as u32 * 1000 - penalty);
+    }
+
+    #[test_case]
+    fn wrong_action_kills_with_one_life(_gba: &mut agb::Gba) {
+        let mut game = GameState::new(Rules::DEFAULT, SEED);
+
+        let action = wrong_action(&game);
+        let events = game.step(buttons_for(&game, action), 1);
+
+        assert_eq!(events, [GameEvent::Died]);
+        assert!(game.is_dead());
+        assert_eq!(game.score(), 0);
+
+        let mut finished = false;
+        for _ in 0..=DEATH_FRAMES + 1 {
+            finished |= game.step(Button::empty(), 1).contains(&GameEvent::Finished);
+        }
+
+        assert!(finished);
+        assert!(game.is_finished());
+    }
+
+    #[test_case]
+    fn wrong_action_costs_a_life(_gba: &mut agb::Gba) {
+        let rules = Rules {
+            lives: 3,
+            ..Rules::DEFAULT
+        };
+        let mut game = GameState::new(rules, SEED);
+
+        let action = wrong_action(&game);
+        let events = game.step(buttons_for(&game, action), 1);
+
+        assert_eq!(events, [GameEvent::Hurt { lives: 2 }]);
+        assert!(game.is_invulnerable());
+        assert!(!game.is_dead());
+    }
+
+    #[test_case]
+    fn timer_running_out_finishes_the_run(_gba: &mut agb::Gba) {
+        let mut game = GameState::new(Rules::DEFAULT, SEED);
+        let duration = Rules::DEFAULT.seconds as u32 * TICKS_PER_SECOND;
+
+        assert!(game.step(Button::empty(), duration - 1).is_empty());
+
+        let events = game.step(Button::empty(), 1);
+        assert_eq!(events, [GameEvent::TimeUp, GameEvent::Finished]);
+        assert!(game.is_finished());
+
+        assert!(game.step(Button::empty(), 1).is_empty());
+    }
+}
@@ -0,0 +1,23 @@
+use agb::{
+    display::{GraphicsFrame, object::Object},
+    include_aseprite,
+};
+
+include_aseprite!(
+    mod heart,
+    "gfx/heart.aseprite",
+);
+
+pub fn draw_hearts(frame: &mut GraphicsFrame<'_>, lives: usize, max_lives: usize) {
+    for index in 0..max_lives {
+        let tag = if index < lives {
+            &heart::FULL
+        } else {
+            &heart::EMPTY
+        };
+
+        Object::new(tag.sprite(0))
+            .set_pos((240 - 8 - 12 * (max_lives - index) as i32, 14))
+            .show(frame);
+    }
+}
@@ -0,0 +1,100 @@
+use alloc::vec::Vec;
+
+use crate::mode::ScoreOrder;
+
+pub const TABLE_LEN: usize = 10;
+pub const NAME_LEN: usize = 6;
+
+#[derive(Clone, Copy)]
+pub struct HighScoreEntry {
+    pub score: u32,
+    name: [u8; NAME_LEN],
+}
+
+impl HighScoreEntry {
+    const EMPTY: Self = Self {
+        score: 0,
+        name: [b' '; NAME_LEN],
+    };
+
+    pub fn name(&self) -> &str {
+        core::str::from_utf8(&self.name).unwrap_or("").trim_end()
+    }
+
+    // A score of zero marks a slot nobody has filled yet
+    pub const fn is_empty(&self) -> bool {
+        self.score == 0
+    }
+}
+
+#[derive(Clone)]
+pub struct HighScores {
+    entries: [HighScoreEntry; TABLE_LEN],
+    order: ScoreOrder,
+}
+
+impl HighScores {
+    pub const fn new(order: ScoreOrder) -> Self {
+        Self {
+            entries: [HighScoreEntry::EMPTY; TABLE_LEN],
+            order,
+        }
+    }
+
+    pub fn entries(&self) -> &[HighScoreEntry] {
+        &self.entries
+    }
+
+    pub const fn best(&self) -> u32 {
+        self.entries[0].score
+    }
+
+    // Returns the rank the score would be inserted at, or `None` if it does
+    // not make the table
+    pub fn rank(&self, score: u32) -> Option<usize> {
+        if score == 0 {
+            return None;
+        }
+
+        self.entries.iter().position(|entry| match self.order {
+            ScoreOrder::Higher => score > entry.score,
+            ScoreOrder::Lower => entry.is_empty() || score < entry.score,
+        })
+    }
+
+    pub fn insert(&mut self, score: u32, name: &str) -> Option<usize> {
+        let rank = self.rank(score)?;
+
+        let mut entry = HighScoreEntry::EMPTY;
+        entry.score = score;
+        for (slot, byte) in entry.name.iter_mut().zip(name.bytes()) {
+            *slot = byte;
+        }
+
+        self.entries[rank..].rotate_right(1);
+        self.entries[rank] = entry;
+        Some(rank)
+    }
+
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        for entry in &self.entries {
+            out.extend_from_slice(&entry.score.to_le_bytes());
+            out.extend_from_slice(&entry.name);
+        }
+    }
+
+    pub fn decode(bytes: &[u8], order: ScoreOrder) -> Option<(Self, &[u8])> {
+        let mut table = Self::new(order);
+        let mut rest = bytes;
+
+        for entry in &mut table.entries {
+            let (score, tail) = rest.split_first_chunk::<4>()?;
+            let (name, tail) = tail.split_first_chunk::<NAME_LEN>()?;
+            entry.score = u32::from_le_bytes(*score);
+            entry.name = *name;
+            rest = tail;
+        }
+
+        Some((table, rest))
+    }
+}
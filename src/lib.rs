@@ -4,204 +4,87 @@
 #![cfg_attr(test, reexport_test_harness_main = "test_main")]
 #![cfg_attr(test, test_runner(agb::test_runner::test_runner))]
 
-use agb::display::Priority;
-use agb::display::font::AlignmentKind;
-use agb::display::tiled::RegularBackgroundSize;
-use agb::display::tiled::{RegularBackground, TileFormat, VRAM_MANAGER};
+use agb::display::tiled::VRAM_MANAGER;
+use agb::include_background_gfx;
 use agb::input::ButtonController;
-use agb::interrupt::VBlank;
 use agb::sound::mixer::Frequency;
-use agb::{fixnum, include_background_gfx};
-use alloc::format;
-use fixnum::vec2;
-use player::*;
+use alloc::boxed::Box;
 
 extern crate alloc;
 
-use crate::binding::{ActionType, BINDINGS};
-use crate::buttons::get_buttons;
-use crate::countdown::Countdown;
-use crate::enemy::setup_enemies;
-use crate::game_over::show_game_over_screen;
-use crate::label::Label;
-use crate::scenario::{Scenario, ScenarioType};
+use crate::countdown::Clock;
+use crate::mode::GameMode;
+use crate::save::Storage;
+use crate::scene::{Context, SceneManager};
 use crate::sfx_manager::Sfx;
-use crate::title_screen::show_title_screen;
+use crate::title_screen::TitleScene;
 
 pub mod binding;
 pub mod buttons;
 pub mod countdown;
 pub mod enemy;
+pub mod fade;
+pub mod game;
 pub mod game_over;
+pub mod game_state;
+pub mod hearts;
+pub mod high_score;
 pub mod label;
+pub mod mode;
+pub mod options;
+pub mod pause;
 pub mod player;
+pub mod popup;
+pub mod replay;
+pub mod rules;
+pub mod save;
 pub mod scenario;
+pub mod scene;
 pub mod sfx_manager;
+pub mod sound_test;
 pub mod title_screen;
 
+// Every screen's backgrounds share one set of palettes, so that two scenes
+// can be shown at once while cross-fading
 include_background_gfx!(
     mod background,
     GAME => deduplicate "gfx/background.png",
     BLUE => deduplicate "gfx/background-full-blue.png",
     RED => deduplicate "gfx/background-full-red.png",
     GREEN => deduplicate "gfx/background-full-green.png",
+    TITLE_SCREEN => deduplicate "gfx/title-screen.png",
+    GAME_OVER => deduplicate "gfx/game-over.png",
 );
 
-pub fn update_full_background(scenario: &Scenario, background: &mut RegularBackground) {
-    let bg = match scenario.state[3] {
-        ScenarioType::Water => &background::BLUE,
-        ScenarioType::Volcano => &background::RED,
-        ScenarioType::Swamp => &background::GREEN,
-    };
-    background.fill_with(bg);
-}
-
-pub fn check_game_over(
-    scenario: &Scenario,
-    scenario_type: ScenarioType,
-    player: &mut Player,
-) -> bool {
-    let wrong_action = scenario.state[3] != scenario_type;
-    if wrong_action {
-        player.kill();
-    }
-    wrong_action
-}
-
-pub fn do_action(scenario: &mut Scenario, action: ActionType, player: &mut Player) {
-    player.perform_action(action);
-    scenario.next();
-}
-
 pub fn main(mut gba: agb::Gba) -> ! {
-    let mut player = Player::new();
-    let mut enemies = setup_enemies();
-    let mut buttons = get_buttons();
-
     let mut sfx = Sfx::create(gba.mixer.mixer(Frequency::Hz18157));
-    let mut gfx = gba.graphics.get();
-
-    show_title_screen(&mut gfx, &mut sfx);
-
-    sfx.stop();
-    sfx.play_game_theme();
+    let gfx = gba.graphics.get();
+    VRAM_MANAGER.set_background_palettes(background::PALETTES);
+
+    let mut storage = Storage::new(&mut gba.save);
+    let save_game = storage.load();
+    sfx.set_volume(save_game.volume);
+
+    let mut timers = gba.timers.timers();
+    let clock = Clock::new(&mut timers.timer2, &mut timers.timer3);
+
+    let context = Context {
+        sfx,
+        input: ButtonController::new(),
+        clock,
+        storage,
+        save_game,
+        mode: GameMode::TimeAttack,
+    };
 
-    let mut enemies_killed: usize;
-    let mut death_counter: usize;
+    SceneManager::new(gfx, context).run(Box::new(TitleScene::new()))
+}
 
-    // Game setup
+#[cfg(test)]
+#[agb::entry]
+fn agb_test_main(_gba: agb::Gba) -> ! {
     loop {
-        let vblank = VBlank::get();
-
-        let mut timers = gba.timers.timers();
-        let mut countdown = Countdown::new(10, &mut timers.timer2, &mut timers.timer3);
-        let mut input = ButtonController::new();
-
-        let mut game_bg = RegularBackground::new(
-            Priority::P3,
-            RegularBackgroundSize::Background32x32,
-            TileFormat::FourBpp,
-        );
-
-        game_bg.fill_with(&background::GAME);
-
-        let mut full_bg = RegularBackground::new(
-            Priority::P1,
-            RegularBackgroundSize::Background32x32,
-            TileFormat::FourBpp,
-        );
-
-        let mut scenario = Scenario::new();
-        scenario.randomize();
-
-        update_full_background(&scenario, &mut full_bg);
-
-        // Game restart
-        loop {
-            VRAM_MANAGER.set_background_palettes(background::PALETTES);
-
-            enemies_killed = 0;
-            death_counter = 0;
-
-            player.reset();
-            countdown.reset();
-            countdown.set_enabled(true);
-
-            // Game update
-            loop {
-                vblank.wait_for_vblank();
-
-                sfx.frame();
-                countdown.update();
-
-                if countdown.seconds_left() <= 0 {
-                    countdown.set_enabled(false);
-                    break;
-                }
-
-                player.update();
-                enemies[3].update();
-
-                let mut score_label = Label::new(
-                    &format!("Score: {enemies_killed}"),
-                    vec2(8, 3),
-                    AlignmentKind::Left,
-                    18,
-                    80,
-                );
-                let mut time_label = Label::new(
-                    &format!("Time: {0}", countdown.seconds_left()),
-                    vec2(0, 3),
-                    AlignmentKind::Right,
-                    16,
-                    232,
-                );
-
-                let mut frame = gfx.frame();
-
-                player.draw(&mut frame);
-
-                for enemy in &mut enemies {
-                    enemy.draw(&mut frame);
-                }
-
-                for button in &mut buttons {
-                    button.show(&mut frame);
-                }
-
-                game_bg.show(&mut frame);
-                full_bg.show(&mut frame);
-
-                scenario.draw(&mut frame);
-
-                score_label.draw(&mut frame);
-                time_label.draw(&mut frame);
-
-                input.update();
-                frame.commit();
-
-                if !player.is_dead() {
-                    for binding in BINDINGS {
-                        if input.is_just_pressed(binding.button)
-                            && !check_game_over(&scenario, binding.scenario, &mut player)
-                        {
-                            do_action(&mut scenario, binding.action, &mut player);
-                            update_full_background(&scenario, &mut full_bg);
-                            enemies_killed += 1;
-                        }
-                    }
-                } else {
-                    countdown.set_enabled(false);
-                    if death_counter > 50 {
-                        break;
-                    }
-                    death_counter += 1;
-                }
-            }
-
-            show_game_over_screen(enemies_killed, &mut gfx, &mut sfx);
-
-            sfx.play_game_theme();
-        }
+        // The tests are run by the entry macro before this is reached
+        agb::syscall::halt();
     }
 }
@@ -0,0 +1,107 @@
+use alloc::{format, string::String};
+
+use crate::game_state::RunSummary;
+use crate::rules::{Rules, TimerMode};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ScoreOrder {
+    Higher,
+    Lower,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum GameMode {
+    TimeAttack,
+    Endless,
+    SuddenDeath,
+    Sprint,
+    Survival,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 5] = [
+        GameMode::TimeAttack,
+        GameMode::Endless,
+        GameMode::SuddenDeath,
+        GameMode::Sprint,
+        GameMode::Survival,
+    ];
+
+    pub const fn index(self) -> usize {
+        self as usize
+    }
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            GameMode::TimeAttack => "Time Attack",
+            GameMode::Endless => "Endless",
+            GameMode::SuddenDeath => "Sudden Death",
+            GameMode::Sprint => "Sprint",
+            GameMode::Survival => "Survival",
+        }
+    }
+
+    pub const fn next(self) -> Self {
+        Self::ALL[(self.index() + 1) % Self::ALL.len()]
+    }
+
+    pub const fn previous(self) -> Self {
+        Self::ALL[(self.index() + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+
+    // Sprint is ranked by the time taken, everything else by points
+    pub const fn score_order(self) -> ScoreOrder {
+        match self {
+            GameMode::Sprint => ScoreOrder::Lower,
+            _ => ScoreOrder::Higher,
+        }
+    }
+
+    // What goes in the high-score table for a run, if anything
+    pub const fn table_score(self, summary: &RunSummary) -> Option<u32> {
+        match self {
+            GameMode::Sprint if summary.completed => Some(summary.millis),
+            GameMode::Sprint => None,
+            _ => Some(summary.score as u32),
+        }
+    }
+
+    pub fn format_score(self, score: u32) -> String {
+        match self.score_order() {
+            ScoreOrder::Higher => format!("{score}"),
+            ScoreOrder::Lower => format!("{}.{:02}s", score / 1000, score % 1000 / 10),
+        }
+    }
+
+    pub const fn rules(self) -> Rules {
+        match self {
+            GameMode::TimeAttack => Rules::DEFAULT,
+            GameMode::Endless => Rules {
+                timer: TimerMode::Off,
+                time_bonus_ms: 0,
+                lives: 3,
+                ..Rules::DEFAULT
+            },
+            GameMode::SuddenDeath => Rules {
+                timer: TimerMode::Off,
+                time_bonus_ms: 0,
+                ..Rules::DEFAULT
+            },
+            GameMode::Sprint => Rules {
+                timer: TimerMode::Stopwatch,
+                target_kills: Some(25),
+                time_bonus_ms: 0,
+                mistake_penalty_ms: Some(2000),
+                // Sprint kills come quickly, so the previews keep up
+                transition_frames: 4,
+                ..Rules::DEFAULT
+            },
+            // Every correct action buys time and every mistake costs some,
+            // so a good player can keep going indefinitely
+            GameMode::Survival => Rules {
+                mistake_penalty_ms: Some(1500),
+                ..Rules::DEFAULT
+            },
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use agb::{
+    display::{GraphicsFrame, font::AlignmentKind},
+    fixnum::vec2,
+    input::{Button, ButtonController},
+};
+use alloc::{format, string::String};
+
+use crate::{
+    binding::{ASSIGNABLE_BUTTONS, Bindings, button_names},
+    label::Label,
+    sfx_manager::{MAX_VOLUME, Volume},
+};
+
+fn volume_bar(level: u8) -> String {
+    let mut bar = String::new();
+    for step in 0..MAX_VOLUME {
+        bar.push(if step < level { '#' } else { '-' });
+    }
+    bar
+}
+
+fn layout(selected: usize, status: &str, bindings: &Bindings, volume: Volume) -> Label {
+    let mut text = String::from("Options");
+
+    let rows = bindings
+        .iter()
+        .map(|binding| {
+            format!(
+                "{0}: {1}",
+                binding.scenario.name(),
+                button_names(binding.buttons)
+            )
+        })
+        .chain([
+            format!("Music: {0}", volume_bar(volume.music)),
+            format!("Effects: {0}", volume_bar(volume.effects)),
+            String::from("Reset defaults"),
+            String::from("Back"),
+        ]);
+
+    for (index, row) in rows.enumerate() {
+        text.push_str(if index == selected { "\n> " } else { "\n  " });
+        text.push_str(&row);
+    }
+
+    if !status.is_empty() {
+        text.push('\n');
+        text.push_str(status);
+    }
+
+    Label::new(&text, vec2(120 - 56, 80 - 40), AlignmentKind::Left, 18, 112)
+}
+
+pub struct OptionsMenu {
+    selected: usize,
+    waiting: bool,
+    message: String,
+    label: Label,
+}
+
+impl OptionsMenu {
+    pub fn new(bindings: &Bindings, volume: Volume) -> Self {
+        Self {
+            selected: 0,
+            waiting: false,
+            message: String::new(),
+            label: layout(0, "", bindings, volume),
+        }
+    }
+
+    fn rows(bindings: &Bindings) -> usize {
+        bindings.iter().count() + 4
+    }
+
+    fn refresh(&mut self, bindings: &Bindings, volume: Volume) {
+        let status = if self.waiting {
+            "Press a button"
+        } else {
+            &self.message
+        };
+        self.label = layout(self.selected, status, bindings, volume);
+    }
+
+    // Returns true once the menu has been closed
+    pub fn update(
+        &mut self,
+        input: &ButtonController,
+        bindings: &mut Bindings,
+        volume: &mut Volume,
+    ) -> bool {
+        let count = bindings.iter().count();
+
+        if self.waiting {
+            if input.is_just_pressed(Button::START) {
+                self.waiting = false;
+                self.refresh(bindings, *volume);
+                return false;
+            }
+
+            let Some(&button) = ASSIGNABLE_BUTTONS
+                .iter()
+                .find(|&&button| input.is_just_pressed(button))
+            else {
+                return false;
+            };
+
+            let scenario = bindings
+                .iter()
+                .nth(self.selected)
+                .map(|binding| binding.scenario);
+
+            if let Some(scenario) = scenario {
+                self.message = match bindings.assign(scenario, button) {
+                    Ok(()) => String::new(),
+                    Err(other) => {
+                        format!("{0} is used by {1}", button_names(button), other.name())
+                    }
+                };
+            }
+
+            self.waiting = false;
+            self.refresh(bindings, *volume);
+            return false;
+        }
+
+        if input.is_just_pressed(Button::B) {
+            return true;
+        }
+
+        if input.is_just_pressed(Button::UP) {
+            self.selected = (self.selected + Self::rows(bindings) - 1) % Self::rows(bindings);
+            self.refresh(bindings, *volume);
+        }
+
+        if input.is_just_pressed(Button::DOWN) {
+            self.selected = (self.selected + 1) % Self::rows(bindings);
+            self.refresh(bindings, *volume);
+        }
+
+        // The two rows after the bindings are the music and effects volume
+        let level = if self.selected == count {
+            Some(&mut volume.music)
+        } else if self.selected == count + 1 {
+            Some(&mut volume.effects)
+        } else {
+            None
+        };
+
+        if let Some(level) = level {
+            if input.is_just_pressed(Button::LEFT) {
+                *level = level.saturating_sub(1);
+            }
+
+            if input.is_just_pressed(Button::RIGHT) {
+                *level = (*level + 1).min(MAX_VOLUME);
+            }
+
+            if input.is_just_pressed(Button::LEFT) || input.is_just_pressed(Button::RIGHT) {
+                self.refresh(bindings, *volume);
+            }
+        }
+
+        if input.is_just_pressed(Button::A) {
+            if self.selected < count {
+                self.waiting = true;
+                self.message = String::new();
+            } else if self.selected < count + 2 {
+                return false;
+            } else if self.selected == count + 2 {
+                bindings.reset();
+                *volume = Volume::DEFAULT;
+                self.message = String::from("Defaults restored");
+            } else {
+                return true;
+            }
+            self.refresh(bindings, *volume);
+        }
+
+        false
+    }
+
+    pub fn draw(&mut self, frame: &mut GraphicsFrame<'_>) {
+        self.label.draw(frame);
+    }
+}
@@ -0,0 +1,80 @@
+use agb::{
+    display::{GraphicsFrame, font::AlignmentKind},
+    fixnum::vec2,
+    input::{Button, ButtonController},
+};
+use alloc::string::String;
+
+use crate::label::Label;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum PauseChoice {
+    Resume,
+    Restart,
+    Quit,
+}
+
+const OPTIONS: [(PauseChoice, &str); 3] = [
+    (PauseChoice::Resume, "Resume"),
+    (PauseChoice::Restart, "Restart"),
+    (PauseChoice::Quit, "Quit to title"),
+];
+
+pub struct PauseMenu {
+    selected: usize,
+    label: Label,
+}
+
+impl PauseMenu {
+    pub fn new() -> Self {
+        Self {
+            selected: 0,
+            label: Self::layout(0),
+        }
+    }
+
+    fn layout(selected: usize) -> Label {
+        let mut text = String::from("Paused");
+        for (index, (_, name)) in OPTIONS.iter().enumerate() {
+            text.push_str(if index == selected { "\n> " } else { "\n  " });
+            text.push_str(name);
+        }
+
+        Label::new(&text, vec2(120 - 40, 80 - 24), AlignmentKind::Left, 18, 80)
+    }
+
+    pub fn update(&mut self, input: &ButtonController) -> Option<PauseChoice> {
+        if input.is_just_pressed(Button::START) || input.is_just_pressed(Button::B) {
+            return Some(PauseChoice::Resume);
+        }
+
+        if input.is_just_pressed(Button::A) {
+            return Some(OPTIONS[self.selected].0);
+        }
+
+        let selected = if input.is_just_pressed(Button::UP) {
+            (self.selected + OPTIONS.len() - 1) % OPTIONS.len()
+        } else if input.is_just_pressed(Button::DOWN) {
+            (self.selected + 1) % OPTIONS.len()
+        } else {
+            self.selected
+        };
+
+        if selected != self.selected {
+            self.selected = selected;
+            self.label = Self::layout(selected);
+        }
+
+        None
+    }
+
+    pub fn draw(&mut self, frame: &mut GraphicsFrame<'_>) {
+        self.label.draw(frame);
+    }
+}
+
+impl Default for PauseMenu {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -10,12 +10,19 @@ include_aseprite! {
     "gfx/player.aseprite"
 }
 
+// How many frames an action or a hit shows before going back to idle
+const ACTION_FRAMES: usize = 7;
+
+// How many pixels a hit pushes the player back
+const KNOCKBACK: i32 = 6;
+
 #[derive(PartialEq)]
 pub enum PlayerState {
     Idle,
     Attack,
     Shield,
     Jump,
+    Hurt,
     Dead,
 }
 
@@ -25,6 +32,8 @@ pub struct Player {
     state: PlayerState,
     accumulator: usize,
     anim_frame: usize,
+    blinking: bool,
+    blink_frame: usize,
 }
 
 impl Player {
@@ -36,12 +45,15 @@ impl Player {
             state: PlayerState::Idle,
             accumulator: 0,
             anim_frame: 0,
+            blinking: false,
+            blink_frame: 0,
         }
     }
 
     pub fn reset(&mut self) {
         self.state = PlayerState::Idle;
         self.counter = num!(0.0);
+        self.blinking = false;
     }
 
     pub fn perform_action(&mut self, action: ActionType) {
@@ -53,6 +65,15 @@ impl Player {
         }
     }
 
+    pub fn hurt(&mut self) {
+        self.accumulator = 0;
+        self.state = PlayerState::Hurt;
+    }
+
+    pub fn set_blinking(&mut self, blinking: bool) {
+        self.blinking = blinking;
+    }
+
     pub fn kill(&mut self) {
         self.state = PlayerState::Dead;
     }
@@ -67,6 +88,7 @@ impl Player {
             PlayerState::Attack => player::ATTACK.sprite(0).into(),
             PlayerState::Shield => player::SHIELD.sprite(0).into(),
             PlayerState::Jump => player::JUMP.sprite(0).into(),
+            PlayerState::Hurt => player::IDLE.sprite(0).into(),
             PlayerState::Dead => player::DEATH.sprite(0).into(),
         };
 
@@ -75,7 +97,9 @@ impl Player {
             return;
         }
 
-        if self.accumulator > 6 {
+        self.blink_frame = self.blink_frame.wrapping_add(1);
+
+        if self.accumulator >= ACTION_FRAMES {
             self.anim_frame += 1;
             self.anim_frame %= 2;
             self.state = PlayerState::Idle;
@@ -86,14 +110,29 @@ impl Player {
     }
 
     pub fn draw(&self, frame: &mut GraphicsFrame<'_>) {
+        if self.blinking && self.blink_frame % 8 >= 4 {
+            return;
+        }
+
         let test = num!(1.0) + ((self.counter / 10) % num!(0.5));
 
+        // A hit knocks the player back and tilts them, easing off as they
+        // recover
+        let recoil = match self.state {
+            PlayerState::Hurt => ACTION_FRAMES.saturating_sub(self.accumulator) as i32,
+            _ => 0,
+        };
+
         let position: Vector2D<Num<i32, 8>> = match self.state {
             PlayerState::Dead => vec2((36 - 6).into(), (71 + 3).into()),
-            _ => vec2(36.into(), 71.into()),
+            _ => vec2(
+                (36 - KNOCKBACK * recoil / ACTION_FRAMES as i32).into(),
+                71.into(),
+            ),
         };
 
-        let rot_mat: AffineMatrix = AffineMatrix::from_rotation(self.counter);
+        let tilt = num!(0.01) * recoil;
+        let rot_mat: AffineMatrix = AffineMatrix::from_rotation(self.counter - tilt);
         let scale_mat: AffineMatrix = AffineMatrix::from_scale(vec2(test, test));
         let pos_mat: AffineMatrix = AffineMatrix::from_translation(position);
 
@@ -0,0 +1,70 @@
+use agb::{
+    display::{GraphicsFrame, font::AlignmentKind},
+    fixnum::{Vector2D, vec2},
+};
+use alloc::{string::String, vec::Vec};
+
+use crate::label::Label;
+
+const POPUP_FRAMES: i32 = 30;
+
+// How far a popup floats up over its lifetime
+const POPUP_RISE: i32 = 15;
+
+struct Popup {
+    text: String,
+    position: Vector2D<i32>,
+    frames: i32,
+}
+
+pub struct Popups {
+    popups: Vec<Popup>,
+}
+
+impl Popups {
+    pub fn new() -> Self {
+        Self { popups: Vec::new() }
+    }
+
+    pub fn clear(&mut self) {
+        self.popups.clear();
+    }
+
+    pub fn spawn(&mut self, text: String, position: Vector2D<i32>) {
+        self.popups.push(Popup {
+            text,
+            position,
+            frames: 0,
+        });
+    }
+
+    pub fn update(&mut self) {
+        for popup in &mut self.popups {
+            popup.frames += 1;
+        }
+        self.popups.retain(|popup| popup.frames < POPUP_FRAMES);
+    }
+
+    pub fn draw(&self, frame: &mut GraphicsFrame<'_>) {
+        for popup in &self.popups {
+            // Float upwards, slowing down over time
+            let frames = popup.frames;
+            let rise =
+                POPUP_RISE * frames * (2 * POPUP_FRAMES - frames) / (POPUP_FRAMES * POPUP_FRAMES);
+            let mut label = Label::new(
+                &popup.text,
+                popup.position - vec2(0, rise),
+                AlignmentKind::Left,
+                18,
+                80,
+            );
+            label.draw(frame);
+        }
+    }
+}
+
+impl Default for Popups {
+    fn default() -> Self {
+        Self::new()
+    }
+}
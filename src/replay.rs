@@ -0,0 +1,192 @@
+use agb::input::Button;
+use alloc::vec::Vec;
+use core::slice::Iter;
+
+use crate::binding::Bindings;
+use crate::game_state::{GameState, RunSummary};
+use crate::rules::Rules;
+
+// Three minutes of frames. Runs without a time limit can go on far longer,
+// and recording them would eventually fill the heap.
+const MAX_FRAMES: usize = 60 * 60 * 3;
+
+#[derive(Clone, Copy)]
+pub struct ReplayFrame {
+    pub buttons: Button,
+    pub elapsed: u32,
+}
+
+pub struct Replay {
+    seed: u32,
+    rules: Rules,
+    bindings: Bindings,
+    frames: Vec<ReplayFrame>,
+}
+
+impl Replay {
+    pub fn new(seed: u32, rules: Rules, bindings: Bindings) -> Self {
+        Self {
+            seed,
+            rules,
+            bindings,
+            frames: Vec::new(),
+        }
+    }
+
+    pub const fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub const fn rules(&self) -> &Rules {
+        &self.rules
+    }
+
+    pub const fn bindings(&self) -> &Bindings {
+        &self.bindings
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    // Returns false once the recording is full or out of memory, after which
+    // the run can't be replayed
+    pub fn record(&mut self, buttons: Button, elapsed: u32) -> bool {
+        if self.frames.len() >= MAX_FRAMES || self.frames.try_reserve(1).is_err() {
+            return false;
+        }
+
+        self.frames.push(ReplayFrame { buttons, elapsed });
+        true
+    }
+
+    pub fn frame(&self, index: usize) -> Option<ReplayFrame> {
+        self.frames.get(index).copied()
+    }
+
+    pub fn playback(&self) -> Playback<'_> {
+        Playback {
+            frames: self.frames.iter(),
+        }
+    }
+}
+
+pub struct Playback<'a> {
+    frames: Iter<'a, ReplayFrame>,
+}
+
+impl Iterator for Playback<'_> {
+    type Item = ReplayFrame;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.frames.next().copied()
+    }
+}
+
+pub const fn just_pressed(buttons: Button, previous: Button) -> Button {
+    buttons.difference(previous)
+}
+
+// Runs a recording through the game rules without touching the hardware and
+// returns how the run ended
+pub fn run(replay: &Replay) -> RunSummary {
+    let mut game = GameState::new(*replay.rules(), replay.seed());
+    game.set_bindings(replay.bindings().clone());
+    let mut previous = Button::empty();
+
+    for frame in replay.playback() {
+        game.step(just_pressed(frame.buttons, previous), frame.elapsed);
+        previous = frame.buttons;
+
+        if game.is_finished() {
+            break;
+        }
+    }
+
+    game.summary()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::countdown::TICKS_PER_SECOND;
+    use crate::game_state::test_helpers::{buttons_for, wrong_action};
+    use crate::mode::GameMode;
+
+    const FRAME: u32 = TICKS_PER_SECOND / 60;
+    const MAX_FRAMES: usize = 10_000;
+
+    // Records a run where the right button is pressed on every other frame,
+    // along with how the live game ended
+    fn record_perfect_run(rules: Rules, seed: u32) -> (Replay, RunSummary) {
+        let bindings = Bindings::new();
+        let mut replay = Replay::new(seed, rules, bindings.clone());
+        let mut game = GameState::new(rules, seed);
+        game.set_bindings(bindings);
+        let mut previous = Button::empty();
+
+        for frame in 0..MAX_FRAMES {
+            let buttons = if frame % 2 == 0 {
+                buttons_for(&game, game.required_action())
+            } else {
+                Button::empty()
+            };
+
+            replay.record(buttons, FRAME);
+            game.step(just_pressed(buttons, previous), FRAME);
+            previous = buttons;
+
+            if game.is_finished() {
+                break;
+            }
+        }
+
+        assert!(game.is_finished());
+        (replay, game.summary())
+    }
+
+    #[test_case]
+    fn replayed_sprint_matches_the_recording(_gba: &mut agb::Gba) {
+        let (replay, recorded) = record_perfect_run(GameMode::Sprint.rules(), 0xC0FFEE);
+        let replayed = run(&replay);
+
+        assert_eq!(replayed.kills, 25);
+        assert!(replayed.completed);
+        assert_eq!(replayed.score, recorded.score);
+        assert_eq!(replayed.millis, recorded.millis);
+        assert_eq!(replayed.best_streak, recorded.best_streak);
+    }
+
+    #[test_case]
+    fn replayed_mistake_scores_nothing(_gba: &mut agb::Gba) {
+        let rules = Rules::DEFAULT;
+        let game = GameState::new(rules, 0xC0FFEE);
+        let wrong = wrong_action(&game);
+
+        let mut replay = Replay::new(0xC0FFEE, rules, Bindings::new());
+        replay.record(buttons_for(&game, wrong), FRAME);
+        for _ in 0..MAX_FRAMES {
+            replay.record(Button::empty(), FRAME);
+        }
+
+        let replayed = run(&replay);
+        assert_eq!(replayed.score, 0);
+        assert_eq!(replayed.kills, 0);
+        assert!(!replayed.completed);
+    }
+
+    #[test_case]
+    fn recording_stops_when_full(_gba: &mut agb::Gba) {
+        let mut replay = Replay::new(0xC0FFEE, Rules::DEFAULT, Bindings::new());
+        for _ in 0..super::MAX_FRAMES {
+            assert!(replay.record(Button::empty(), FRAME));
+        }
+
+        assert!(!replay.record(Button::empty(), FRAME));
+        assert_eq!(replay.len(), super::MAX_FRAMES);
+    }
+}
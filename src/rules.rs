@@ -0,0 +1,84 @@
+use crate::countdown::Countdown;
+use crate::scenario::DEFAULT_TRANSITION_FRAMES;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TimerMode {
+    Countdown,
+    Stopwatch,
+    Off,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Rules {
+    pub timer: TimerMode,
+    pub seconds: usize,
+    // The run is won once this many enemies have been defeated
+    pub target_kills: Option<usize>,
+    // Time added for a correct action while the score is still zero
+    pub time_bonus_ms: u32,
+    // The bonus halves once the score reaches this value, and keeps shrinking
+    pub bonus_half_score: u32,
+    pub min_time_bonus_ms: u32,
+    // Mistakes allowed before the run ends; 1 means the first mistake kills
+    pub lives: usize,
+    // How long mistakes are ignored after losing a life
+    pub invulnerability_ms: u32,
+    // If set, a wrong action costs this much time instead of a life
+    pub mistake_penalty_ms: Option<u32>,
+    // The streak is lost if the next correct action takes longer than this
+    pub combo_window_ms: u32,
+    // Every this many actions in a streak adds one to the multiplier
+    pub combo_step: usize,
+    pub max_multiplier: usize,
+    // How long the scenario previews take to slide forward after a kill
+    pub transition_frames: usize,
+}
+
+impl Rules {
+    pub const DEFAULT: Self = Self {
+        timer: TimerMode::Countdown,
+        seconds: 10,
+        target_kills: None,
+        time_bonus_ms: 600,
+        bonus_half_score: 20,
+        min_time_bonus_ms: 50,
+        lives: 1,
+        invulnerability_ms: 1000,
+        mistake_penalty_ms: None,
+        combo_window_ms: 1500,
+        combo_step: 5,
+        max_multiplier: 4,
+        transition_frames: DEFAULT_TRANSITION_FRAMES,
+    };
+
+    pub fn countdown(&self) -> Countdown {
+        match self.timer {
+            TimerMode::Countdown => Countdown::new(self.seconds),
+            TimerMode::Stopwatch | TimerMode::Off => Countdown::stopwatch(),
+        }
+    }
+
+    pub const fn multiplier(&self, streak: usize) -> usize {
+        let multiplier = 1 + streak / self.combo_step;
+        if multiplier > self.max_multiplier {
+            self.max_multiplier
+        } else {
+            multiplier
+        }
+    }
+
+    pub const fn time_bonus(&self, score: usize) -> u32 {
+        if self.time_bonus_ms == 0 || !matches!(self.timer, TimerMode::Countdown) {
+            return 0;
+        }
+
+        let half = self.bonus_half_score as u64;
+        let bonus = (self.time_bonus_ms as u64 * half / (half + score as u64).max(1)) as u32;
+
+        if bonus < self.min_time_bonus_ms {
+            self.min_time_bonus_ms
+        } else {
+            bonus
+        }
+    }
+}
@@ -0,0 +1,256 @@
+use agb::save::{SaveData, SaveManager};
+use alloc::vec::Vec;
+
+use crate::binding::Bindings;
+use crate::high_score::HighScores;
+use crate::mode::GameMode;
+use crate::sfx_manager::Volume;
+
+const MAGIC: [u8; 4] = *b"PLKO";
+const VERSION: u16 = 8;
+// Magic, version, checksum and payload length
+const HEADER_LEN: usize = 10;
+
+#[derive(Clone)]
+pub struct SaveGame {
+    // One table per mode, indexed by `GameMode::index`
+    high_scores: [HighScores; GameMode::ALL.len()],
+    pub bindings: Bindings,
+    pub volume: Volume,
+}
+
+impl SaveGame {
+    pub fn new() -> Self {
+        Self {
+            high_scores: GameMode::ALL.map(|mode| HighScores::new(mode.score_order())),
+            bindings: Bindings::new(),
+            volume: Volume::DEFAULT,
+        }
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for table in &self.high_scores {
+            table.encode(&mut payload);
+        }
+        self.bindings.encode(&mut payload);
+        self.volume.encode(&mut payload);
+        payload
+    }
+
+    fn decode(payload: &[u8]) -> Option<Self> {
+        let mut save_game = Self::new();
+        let mut rest = payload;
+
+        for (table, mode) in save_game.high_scores.iter_mut().zip(GameMode::ALL) {
+            let (decoded, tail) = HighScores::decode(rest, mode.score_order())?;
+            *table = decoded;
+            rest = tail;
+        }
+
+        let (bindings, rest) = Bindings::decode(rest)?;
+        let (volume, _) = Volume::decode(rest)?;
+        save_game.bindings = bindings;
+        save_game.volume = volume;
+        Some(save_game)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        with_header(&self.encode())
+    }
+
+    pub fn high_scores(&self, mode: GameMode) -> &HighScores {
+        &self.high_scores[mode.index()]
+    }
+
+    pub fn high_scores_mut(&mut self, mode: GameMode) -> &mut HighScores {
+        &mut self.high_scores[mode.index()]
+    }
+}
+
+impl Default for SaveGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Fletcher-16 over the payload, so a blank or half-written save is rejected
+fn checksum(bytes: &[u8]) -> u16 {
+    let mut sum1: u16 = 0;
+    let mut sum2: u16 = 0;
+    for &byte in bytes {
+        sum1 = (sum1 + byte as u16) % 255;
+        sum2 = (sum2 + sum1) % 255;
+    }
+    (sum2 << 8) | sum1
+}
+
+// The header and payload as written to SRAM. The payload length is stored,
+// since it grows when scenarios are added.
+fn with_header(payload: &[u8]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN + payload.len());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.extend_from_slice(&VERSION.to_le_bytes());
+    bytes.extend_from_slice(&checksum(payload).to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+// The checksum and length of the payload, if the header is from this version
+fn read_header(header: &[u8; HEADER_LEN]) -> Option<(u16, usize)> {
+    let (magic, rest) = header.split_first_chunk::<4>()?;
+    let (version, rest) = rest.split_first_chunk::<2>()?;
+    let (sum, rest) = rest.split_first_chunk::<2>()?;
+    let (len, _) = rest.split_first_chunk::<2>()?;
+
+    if *magic != MAGIC || u16::from_le_bytes(*version) != VERSION {
+        return None;
+    }
+
+    Some((
+        u16::from_le_bytes(*sum),
+        usize::from(u16::from_le_bytes(*len)),
+    ))
+}
+
+fn read_payload(sum: u16, payload: &[u8]) -> Option<SaveGame> {
+    if checksum(payload) != sum {
+        return None;
+    }
+
+    SaveGame::decode(payload)
+}
+
+pub struct Storage {
+    data: Option<SaveData>,
+}
+
+impl Storage {
+    pub fn new(save: &mut SaveManager) -> Self {
+        save.init_sram();
+        Self {
+            data: save.access().ok(),
+        }
+    }
+
+    // Loads the save, reinitialising it if the header or checksum don't match
+    pub fn load(&mut self) -> SaveGame {
+        match self.read() {
+            Some(save_game) => save_game,
+            None => {
+                let save_game = SaveGame::new();
+                self.store(&save_game);
+                save_game
+            }
+        }
+    }
+
+    fn read(&mut self) -> Option<SaveGame> {
+        let data = self.data.as_mut()?;
+
+        let mut header = [0; HEADER_LEN];
+        data.read(0, &mut header).ok()?;
+
+        let (sum, len) = read_header(&header)?;
+
+        let mut payload = alloc::vec![0; len];
+        data.read(HEADER_LEN, &mut payload).ok()?;
+
+        read_payload(sum, &payload)
+    }
+
+    pub fn store(&mut self, save_game: &SaveGame) {
+        let Some(data) = self.data.as_mut() else {
+            return;
+        };
+
+        let bytes = save_game.to_bytes();
+
+        if let Ok(mut block) = data.prepare_write(0..bytes.len()) {
+            let _ = block.write(0, &bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scenario::ScenarioType;
+    use agb::input::Button;
+
+    fn buttons(bindings: &Bindings, index: usize) -> Option<Button> {
+        bindings.iter().nth(index).map(|binding| binding.buttons)
+    }
+
+    // Reads the save back the way `Storage::read` does
+    fn load(bytes: &[u8]) -> Option<SaveGame> {
+        let (header, payload) = bytes.split_first_chunk::<HEADER_LEN>()?;
+        let (sum, len) = read_header(header)?;
+        read_payload(sum, payload.get(..len)?)
+    }
+
+    // A save written before the last scenario was added, by dropping its
+    // binding from the payload
+    fn save_without_last_scenario(save_game: &SaveGame) -> Vec<u8> {
+        let mut payload = Vec::new();
+        for table in &save_game.high_scores {
+            table.encode(&mut payload);
+        }
+
+        let mut bindings = Vec::new();
+        save_game.bindings.encode(&mut bindings);
+        let (&count, buttons) = bindings.split_first().unwrap();
+        payload.push(count - 1);
+        payload.extend_from_slice(&buttons[..buttons.len() - 2]);
+
+        save_game.volume.encode(&mut payload);
+
+        with_header(&payload)
+    }
+
+    #[test_case]
+    fn save_round_trips(_gba: &mut agb::Gba) {
+        let mut save_game = SaveGame::new();
+        save_game.volume.music = 3;
+        save_game
+            .high_scores_mut(GameMode::ALL[0])
+            .insert(1234, "ABC");
+
+        let loaded = load(&save_game.to_bytes()).unwrap();
+
+        assert_eq!(loaded.volume, save_game.volume);
+        assert_eq!(
+            loaded.high_scores(GameMode::ALL[0]).entries()[0].score,
+            1234
+        );
+    }
+
+    #[test_case]
+    fn save_from_before_a_new_scenario_loads(_gba: &mut agb::Gba) {
+        let mut save_game = SaveGame::new();
+        save_game.volume.effects = 5;
+        let first = ScenarioType::all().next().unwrap();
+        save_game.bindings.assign(first, Button::UP).unwrap();
+        let last = ScenarioType::all().count() - 1;
+
+        let loaded = load(&save_without_last_scenario(&save_game)).unwrap();
+
+        // The remap is kept and the new scenario starts on its default
+        assert_eq!(loaded.volume, save_game.volume);
+        assert_eq!(buttons(&loaded.bindings, 0), Some(Button::UP));
+        assert_eq!(
+            buttons(&loaded.bindings, last),
+            buttons(&Bindings::new(), last)
+        );
+    }
+
+    #[test_case]
+    fn corrupt_save_is_rejected(_gba: &mut agb::Gba) {
+        let mut bytes = SaveGame::new().to_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xFF;
+
+        assert!(load(&bytes).is_none());
+    }
+}
@@ -1,10 +1,21 @@
 use agb::{
     display::{
-        GraphicsFrame,
-        object::{Object, Tag},
+        AffineMatrix, GraphicsFrame, Priority,
+        object::{AffineMode, Object, ObjectAffine, Tag},
+        tiled::{BackgroundId, RegularBackground, RegularBackgroundSize, TileData, TileFormat},
     },
+    fixnum::{Num, num, vec2},
     include_aseprite,
-    rng::{self},
+    input::Button,
+    rng::RandomNumberGenerator,
+};
+use alloc::vec::Vec;
+
+use crate::{
+    background,
+    binding::ActionType,
+    enemy::EnemyKind,
+    fade::{Fade, FadeStyle},
 };
 
 include_aseprite! {
@@ -22,55 +33,222 @@ include_aseprite! {
     "gfx/backgrounds-green.aseprite",
 }
 
-enum ScenarioSize {
-    Small,
-    Medium,
-    Big,
+include_aseprite! {
+    mod hints,
+    "gfx/buttons.aseprite",
+}
+
+pub struct ScenarioDef {
+    pub name: &'static str,
+    pub small: &'static Tag,
+    pub medium: &'static Tag,
+    pub big: &'static Tag,
+    pub background: &'static TileData,
+    // The coloured dot shown with the buttons bound to this scenario
+    pub hint: &'static Tag,
+    pub action: ActionType,
+    pub default_buttons: Button,
+    pub enemies: &'static [EnemyKind],
 }
 
+// Every scenario type is declared here, and nothing else needs to change to
+// add one: the bindings, button hints and save layout all follow this list,
+// and saves from before it was added still load.
+static SCENARIOS: &[ScenarioDef] = &[
+    ScenarioDef {
+        name: "Water",
+        small: &blue::SMALL,
+        medium: &blue::MEDIUM,
+        big: &blue::BIG,
+        background: &background::BLUE,
+        hint: &hints::BLUE,
+        action: ActionType::Attack,
+        default_buttons: Button::L,
+        enemies: &[EnemyKind::Lercio],
+    },
+    ScenarioDef {
+        name: "Volcano",
+        small: &red::SMALL,
+        medium: &red::MEDIUM,
+        big: &red::BIG,
+        background: &background::RED,
+        hint: &hints::RED,
+        action: ActionType::Jump,
+        default_buttons: Button::R,
+        enemies: &[EnemyKind::BigRock],
+    },
+    ScenarioDef {
+        name: "Swamp",
+        small: &green::SMALL,
+        medium: &green::MEDIUM,
+        big: &green::BIG,
+        background: &background::GREEN,
+        hint: &hints::GREEN,
+        action: ActionType::Shield,
+        default_buttons: Button::A.union(Button::B),
+        enemies: &[EnemyKind::Goblin, EnemyKind::Mage],
+    },
+];
+
 #[derive(Copy, Clone, PartialEq, Debug)]
-pub enum ScenarioType {
-    Water,
-    Volcano,
-    Swamp,
-}
-
-const fn get_object(s_size: &ScenarioSize, s_type: &ScenarioType) -> &'static Tag {
-    match s_type {
-        ScenarioType::Water => match s_size {
-            ScenarioSize::Small => &blue::SMALL,
-            ScenarioSize::Medium => &blue::MEDIUM,
-            ScenarioSize::Big => &blue::BIG,
-        },
-        ScenarioType::Volcano => match s_size {
-            ScenarioSize::Small => &red::SMALL,
-            ScenarioSize::Medium => &red::MEDIUM,
-            ScenarioSize::Big => &red::BIG,
-        },
-        ScenarioType::Swamp => match s_size {
-            ScenarioSize::Small => &green::SMALL,
-            ScenarioSize::Medium => &green::MEDIUM,
-            ScenarioSize::Big => &green::BIG,
-        },
+pub struct ScenarioType(usize);
+
+impl ScenarioType {
+    pub fn all() -> impl Iterator<Item = ScenarioType> {
+        (0..SCENARIOS.len()).map(ScenarioType)
+    }
+
+    pub fn def(self) -> &'static ScenarioDef {
+        &SCENARIOS[self.0]
     }
+
+    pub fn name(self) -> &'static str {
+        self.def().name
+    }
+}
+
+fn rng_from_seed(seed: u32) -> RandomNumberGenerator {
+    RandomNumberGenerator::new_with_seed([seed, seed ^ 0x9E37_79B9, !seed, seed.rotate_left(16)])
 }
 
 pub struct Scenario {
     pub state: [ScenarioType; 4],
+    pub enemies: [EnemyKind; 4],
+    rng: RandomNumberGenerator,
+    seed: u32,
+}
+
+impl Scenario {
+    pub fn new(seed: u32) -> Self {
+        let mut scenario = Self {
+            state: [ScenarioType(0); 4],
+            enemies: [EnemyKind::Lercio; 4],
+            rng: rng_from_seed(seed),
+            seed,
+        };
+        scenario.randomize();
+        scenario
+    }
+
+    pub const fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    pub const fn current(&self) -> ScenarioType {
+        self.state[3]
+    }
+
+    pub const fn current_enemy(&self) -> EnemyKind {
+        self.enemies[3]
+    }
+
+    pub fn reseed(&mut self, seed: u32) {
+        self.rng = rng_from_seed(seed);
+        self.seed = seed;
+        self.randomize();
+    }
+
+    pub fn next_seed(&mut self) -> u32 {
+        self.rng.next_i32() as u32
+    }
+
+    fn random_type(&mut self) -> ScenarioType {
+        ScenarioType(self.rng.next_i32() as usize % SCENARIOS.len())
+    }
+
+    fn random_enemy(&mut self, scenario: ScenarioType) -> EnemyKind {
+        let enemies = scenario.def().enemies;
+        enemies[self.rng.next_i32() as usize % enemies.len()]
+    }
+
+    pub fn randomize(&mut self) {
+        self.state = core::array::from_fn(|_| self.random_type());
+        self.enemies = core::array::from_fn(|slot| self.random_enemy(self.state[slot]));
+    }
+
+    pub fn next(&mut self) {
+        self.state.rotate_right(1);
+        self.enemies.rotate_right(1);
+        self.state[0] = self.random_type();
+        self.enemies[0] = self.random_enemy(self.state[0]);
+    }
+}
+
+// Where the small, medium and big previews sit, followed by where the big
+// preview slides to as it becomes the full background
+const LAYER_POSITIONS: [(i32, i32); 4] = [(80, 8), (69, 16), (56, 24), (43, 32)];
+
+// How many 32x8 strips make up the small, medium and big previews
+const LAYER_STRIPS: [usize; 3] = [3, 4, 4];
+
+pub const DEFAULT_TRANSITION_FRAMES: usize = 8;
+
+// How much bigger a layer's art is drawn in the next slot, so each preview
+// grows into the art that replaces it
+fn layer_growth(layer: usize) -> Num<i32, 8> {
+    match layer {
+        0 => num!(1.33),
+        _ => num!(1.2),
+    }
+}
+
+fn layer_tag(def: &ScenarioDef, layer: usize) -> &'static Tag {
+    match layer {
+        0 => def.small,
+        1 => def.medium,
+        _ => def.big,
+    }
+}
+
+fn place_layer(sprites: &mut [Object], tag: &Tag, (x, y): (i32, i32)) {
+    for (index, sprite) in sprites.iter_mut().enumerate() {
+        sprite.set_sprite(tag.sprite(index));
+        sprite.set_pos((x + 32 * index as i32, y));
+    }
+}
+
+// Draws a layer scaled by `scale` from its top left corner at `(x, y)`
+fn draw_scaled(
+    frame: &mut GraphicsFrame<'_>,
+    tag: &Tag,
+    strips: usize,
+    (x, y): (i32, i32),
+    scale: Num<i32, 8>,
+) {
+    let transform = AffineMatrix::from_scale(vec2(scale, scale));
+
+    for index in 0..strips {
+        // Double size sprites are drawn centred in a box twice their size
+        let centre = vec2(
+            Num::new(x) + scale * (32 * index as i32 + 16),
+            Num::new(y) + scale * 4,
+        );
+
+        ObjectAffine::new(
+            tag.sprite(index).into(),
+            transform.into(),
+            AffineMode::AffineDouble,
+        )
+        .set_pos((centre - vec2(num!(32.0), num!(8.0))).round())
+        .show(frame);
+    }
+}
+
+pub struct ScenarioView {
     small_sprite: [Object; 3],
     medium_sprite: [Object; 4],
     big_sprite: [Object; 4],
+    full_bg: RegularBackground,
+    // The full background being faded out during a transition
+    previous_bg: RegularBackground,
+    state: [ScenarioType; 4],
+    transition_frames: usize,
+    transition: Option<usize>,
 }
 
-impl Scenario {
+impl ScenarioView {
     pub fn new() -> Self {
         Self {
-            state: [
-                ScenarioType::Water,
-                ScenarioType::Water,
-                ScenarioType::Water,
-                ScenarioType::Water,
-            ],
             small_sprite: [
                 Object::new(blue::SMALL.sprite(0)),
                 Object::new(blue::SMALL.sprite(1)),
@@ -88,86 +266,185 @@ impl Scenario {
                 Object::new(blue::BIG.sprite(2)),
                 Object::new(blue::BIG.sprite(3)),
             ],
+            full_bg: RegularBackground::new(
+                Priority::P1,
+                RegularBackgroundSize::Background32x32,
+                TileFormat::FourBpp,
+            ),
+            previous_bg: RegularBackground::new(
+                Priority::P1,
+                RegularBackgroundSize::Background32x32,
+                TileFormat::FourBpp,
+            ),
+            state: [ScenarioType(0); 4],
+            transition_frames: DEFAULT_TRANSITION_FRAMES,
+            transition: None,
         }
     }
 
-    pub fn randomize(&mut self) {
-        let scenarios = [
-            ScenarioType::Water,
-            ScenarioType::Volcano,
-            ScenarioType::Swamp,
-        ];
+    pub fn set_transition_frames(&mut self, frames: usize) {
+        self.transition_frames = frames;
+    }
 
-        self.state = [
-            scenarios[rng::next_i32() as usize % 3],
-            scenarios[rng::next_i32() as usize % 3],
-            scenarios[rng::next_i32() as usize % 3],
-            scenarios[rng::next_i32() as usize % 3],
-        ];
+    // Shows the scenario straight away, without a transition
+    pub fn update(&mut self, scenario: &Scenario) {
+        self.state = scenario.state;
+        self.full_bg.fill_with(self.state[3].def().background);
+        self.place_previews();
+    }
+
+    fn place_previews(&mut self) {
+        self.transition = None;
 
-        self.update();
+        place_layer(
+            &mut self.small_sprite,
+            self.state[0].def().small,
+            LAYER_POSITIONS[0],
+        );
+        place_layer(
+            &mut self.medium_sprite,
+            self.state[1].def().medium,
+            LAYER_POSITIONS[1],
+        );
+        place_layer(
+            &mut self.big_sprite,
+            self.state[2].def().big,
+            LAYER_POSITIONS[2],
+        );
     }
 
-    pub fn next(&mut self) {
-        let scenarios = [
-            ScenarioType::Water,
-            ScenarioType::Volcano,
-            ScenarioType::Swamp,
-        ];
+    // Moves every preview one slot forward after `Scenario::next`, each one
+    // sliding and growing into the next slot while a new small preview grows
+    // in. The big preview grows towards the full background, which
+    // cross-fades to the new scenario.
+    pub fn transition(&mut self, scenario: &Scenario) {
+        self.previous_bg.fill_with(self.state[3].def().background);
 
-        self.state.rotate_right(1);
-        self.state[0] = scenarios[rng::next_i32() as usize % 3];
+        self.state = scenario.state;
+        self.full_bg.fill_with(self.state[3].def().background);
+
+        if self.transition_frames == 0 {
+            self.place_previews();
+            return;
+        }
+
+        self.transition = Some(0);
+    }
 
-        self.update();
+    pub fn animate(&mut self) {
+        let Some(frame) = self.transition else {
+            return;
+        };
+
+        let frame = frame + 1;
+        if frame >= self.transition_frames {
+            self.place_previews();
+        } else {
+            self.transition = Some(frame);
+        }
     }
 
-    fn update(&mut self) {
-        let small = get_object(&ScenarioSize::Small, &self.state[0]);
+    // Each layer shows what it showed before the advance, on its way to the
+    // next slot
+    fn draw_transition(&self, frame: &mut GraphicsFrame<'_>, progress: usize) {
+        let frames = self.transition_frames as i32;
+        let progress = progress as i32;
+        let amount = Num::<i32, 8>::new(progress) / frames;
+
+        if progress > 0 {
+            draw_scaled(
+                frame,
+                self.state[0].def().small,
+                LAYER_STRIPS[0],
+                LAYER_POSITIONS[0],
+                amount,
+            );
+        }
 
-        self.small_sprite[0].set_sprite(small.sprite(0));
-        self.small_sprite[1].set_sprite(small.sprite(1));
-        self.small_sprite[2].set_sprite(small.sprite(2));
+        for (layer, strips) in LAYER_STRIPS.into_iter().enumerate() {
+            let (from_x, from_y) = LAYER_POSITIONS[layer];
+            let (to_x, to_y) = LAYER_POSITIONS[layer + 1];
+            let position = (
+                from_x + (to_x - from_x) * progress / frames,
+                from_y + (to_y - from_y) * progress / frames,
+            );
+            let scale = num!(1.0) + (layer_growth(layer) - num!(1.0)) * amount;
 
-        self.small_sprite[0].set_pos((80, 8));
-        self.small_sprite[1].set_pos((80 + 32, 8));
-        self.small_sprite[2].set_pos((80 + 64, 8));
+            let tag = layer_tag(self.state[layer + 1].def(), layer);
+            draw_scaled(frame, tag, strips, position, scale);
+        }
+    }
 
-        let medium = get_object(&ScenarioSize::Medium, &self.state[1]);
+    pub fn draw(&self, frame: &mut GraphicsFrame<'_>, backgrounds: &mut Vec<BackgroundId>) {
+        let Some(progress) = self.transition else {
+            for sprite in self
+                .small_sprite
+                .iter()
+                .chain(&self.medium_sprite)
+                .chain(&self.big_sprite)
+            {
+                sprite.show(frame);
+            }
 
-        self.medium_sprite[0].set_sprite(medium.sprite(0));
-        self.medium_sprite[1].set_sprite(medium.sprite(1));
-        self.medium_sprite[2].set_sprite(medium.sprite(2));
-        self.medium_sprite[3].set_sprite(medium.sprite(3));
+            backgrounds.push(self.full_bg.show(frame));
+            return;
+        };
 
-        self.medium_sprite[0].set_pos((69, 16));
-        self.medium_sprite[1].set_pos((69 + 32, 16));
-        self.medium_sprite[2].set_pos((69 + 64, 16));
-        self.medium_sprite[3].set_pos((69 + 96, 16));
+        self.draw_transition(frame, progress);
 
-        let big = get_object(&ScenarioSize::Big, &self.state[2]);
+        // Shown first so it wins ties in priority with the new background
+        let previous = self.previous_bg.show(frame);
+        let full = self.full_bg.show(frame);
 
-        self.big_sprite[0].set_sprite(big.sprite(0));
-        self.big_sprite[1].set_sprite(big.sprite(1));
-        self.big_sprite[2].set_sprite(big.sprite(2));
-        self.big_sprite[3].set_sprite(big.sprite(3));
+        let fade = Fade {
+            style: FadeStyle::Cross,
+            frames: self.transition_frames,
+        };
+        fade.cross(frame, &[previous], &[full], progress);
 
-        self.big_sprite[0].set_pos((56, 24));
-        self.big_sprite[1].set_pos((56 + 32, 24));
-        self.big_sprite[2].set_pos((56 + 64, 24));
-        self.big_sprite[3].set_pos((56 + 96, 24));
+        backgrounds.push(previous);
+        backgrounds.push(full);
     }
+}
 
-    pub fn draw(&mut self, frame: &mut GraphicsFrame<'_>) {
-        for sprite in &self.small_sprite {
-            sprite.show(frame);
-        }
+impl Default for ScenarioView {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        for sprite in &self.medium_sprite {
-            sprite.show(frame);
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lineup(seed: u32) -> Vec<([ScenarioType; 4], [EnemyKind; 4])> {
+        let mut scenario = Scenario::new(seed);
+        let mut lineup = Vec::new();
 
-        for sprite in &self.big_sprite {
-            sprite.show(frame);
+        for _ in 0..16 {
+            lineup.push((scenario.state, scenario.enemies));
+            scenario.next();
         }
+
+        lineup
+    }
+
+    #[test_case]
+    fn same_seed_gives_same_scenarios(_gba: &mut agb::Gba) {
+        assert_eq!(lineup(42), lineup(42));
+    }
+
+    #[test_case]
+    fn different_seeds_diverge(_gba: &mut agb::Gba) {
+        assert_ne!(lineup(42), lineup(43));
+    }
+
+    #[test_case]
+    fn reseed_matches_a_new_scenario(_gba: &mut agb::Gba) {
+        let mut scenario = Scenario::new(1);
+        scenario.reseed(42);
+
+        assert_eq!(scenario.state, Scenario::new(42).state);
+        assert_eq!(scenario.enemies, Scenario::new(42).enemies);
     }
 }
@@ -0,0 +1,213 @@
+use agb::{
+    display::{Graphics, GraphicsFrame, tiled::BackgroundId},
+    input::ButtonController,
+    interrupt::VBlank,
+};
+use alloc::{boxed::Box, vec::Vec};
+
+use crate::{
+    countdown::Clock,
+    fade::{Fade, FadeStyle},
+    mode::GameMode,
+    save::{SaveGame, Storage},
+    sfx_manager::Sfx,
+};
+
+pub enum Transition {
+    Stay,
+    Replace(Box<dyn Scene>),
+    Fade(Box<dyn Scene>, Fade),
+}
+
+// State shared by every scene, owned by the manager so it outlives them
+pub struct Context<'a> {
+    pub sfx: Sfx<'a>,
+    pub input: ButtonController,
+    pub clock: Clock<'a>,
+    pub storage: Storage,
+    pub save_game: SaveGame,
+    pub mode: GameMode,
+}
+
+// A screen of the game. `enter` runs before the first update and `exit` after
+// the update that asked to leave, so scenes can start and stop their music or
+// set up palettes without knowing what comes before or after them.
+pub trait Scene {
+    fn enter(&mut self, _ctx: &mut Context<'_>) {}
+
+    fn update(&mut self, ctx: &mut Context<'_>) -> Transition;
+
+    // Every background shown is pushed to `backgrounds` so fades cover it
+    fn draw(&mut self, frame: &mut GraphicsFrame<'_>, backgrounds: &mut Vec<BackgroundId>);
+
+    fn exit(&mut self, _ctx: &mut Context<'_>) {}
+}
+
+enum Fading {
+    Out {
+        next: Box<dyn Scene>,
+        fade: Fade,
+        frame: usize,
+    },
+    In {
+        fade: Fade,
+        frame: usize,
+    },
+    // The new scene has been entered, and the old one is only drawn
+    Cross {
+        previous: Box<dyn Scene>,
+        fade: Fade,
+        frame: usize,
+    },
+}
+
+pub struct SceneManager<'a> {
+    gfx: Graphics<'a>,
+    vblank: VBlank,
+    context: Context<'a>,
+    backgrounds: Vec<BackgroundId>,
+    previous_backgrounds: Vec<BackgroundId>,
+}
+
+impl<'a> SceneManager<'a> {
+    pub fn new(gfx: Graphics<'a>, context: Context<'a>) -> Self {
+        Self {
+            gfx,
+            vblank: VBlank::get(),
+            context,
+            backgrounds: Vec::new(),
+            previous_backgrounds: Vec::new(),
+        }
+    }
+
+    // Mixes between each step, since building, leaving and entering a scene
+    // can each take longer than a frame
+    fn switch(&mut self, scene: &mut Box<dyn Scene>, next: Box<dyn Scene>) {
+        self.context.sfx.mix();
+        scene.exit(&mut self.context);
+        self.context.sfx.mix();
+        *scene = next;
+        scene.enter(&mut self.context);
+        self.context.sfx.mix();
+    }
+
+    // Scenes are not updated while fading, so a fade never eats input and the
+    // clock is reset once the new scene is fully shown. A cross-fade enters
+    // the new scene at the start and keeps drawing the old one until the end.
+    pub fn run(&mut self, mut scene: Box<dyn Scene>) -> ! {
+        scene.enter(&mut self.context);
+        self.context.sfx.mix();
+
+        let mut fading: Option<Fading> = None;
+
+        loop {
+            self.vblank.wait_for_vblank();
+
+            // Music only advances here, scene switches also mix so a slow
+            // one doesn't run out of audio
+            self.context.sfx.frame();
+            self.context.input.update();
+
+            fading = match fading.take() {
+                None => match scene.update(&mut self.context) {
+                    Transition::Stay => None,
+                    Transition::Replace(next) => {
+                        self.switch(&mut scene, next);
+                        None
+                    }
+                    Transition::Fade(next, fade) if fade.style == FadeStyle::Cross => {
+                        self.context.sfx.mix();
+                        let mut previous = core::mem::replace(&mut scene, next);
+                        previous.exit(&mut self.context);
+                        self.context.sfx.mix();
+                        scene.enter(&mut self.context);
+                        self.context.sfx.mix();
+                        Some(Fading::Cross {
+                            previous,
+                            fade,
+                            frame: 0,
+                        })
+                    }
+                    Transition::Fade(next, fade) => Some(Fading::Out {
+                        next,
+                        fade,
+                        frame: 0,
+                    }),
+                },
+                Some(Fading::Out { next, fade, frame }) if frame >= fade.frames => {
+                    self.switch(&mut scene, next);
+                    Some(Fading::In { fade, frame: 0 })
+                }
+                Some(Fading::Out { next, fade, frame }) => Some(Fading::Out {
+                    next,
+                    fade,
+                    frame: frame + 1,
+                }),
+                Some(Fading::In { fade, frame }) if frame >= fade.frames => {
+                    self.context.clock.reset();
+                    None
+                }
+                Some(Fading::In { fade, frame }) => Some(Fading::In {
+                    fade,
+                    frame: frame + 1,
+                }),
+                Some(Fading::Cross { fade, frame, .. }) if frame >= fade.frames => {
+                    self.context.clock.reset();
+                    None
+                }
+                Some(Fading::Cross {
+                    previous,
+                    fade,
+                    frame,
+                }) => Some(Fading::Cross {
+                    previous,
+                    fade,
+                    frame: frame + 1,
+                }),
+            };
+
+            self.backgrounds.clear();
+            self.previous_backgrounds.clear();
+
+            let mut frame = self.gfx.frame();
+
+            // Drawn first so its backgrounds win ties in priority
+            if let Some(Fading::Cross { previous, .. }) = fading.as_mut() {
+                previous.draw(&mut frame, &mut self.previous_backgrounds);
+            }
+
+            scene.draw(&mut frame, &mut self.backgrounds);
+
+            match &fading {
+                Some(Fading::Out {
+                    fade,
+                    frame: progress,
+                    ..
+                }) => {
+                    fade.apply(&mut frame, &self.backgrounds, *progress);
+                }
+                Some(Fading::In {
+                    fade,
+                    frame: progress,
+                }) => {
+                    fade.apply(&mut frame, &self.backgrounds, fade.frames - progress);
+                }
+                Some(Fading::Cross {
+                    fade,
+                    frame: progress,
+                    ..
+                }) => {
+                    fade.cross(
+                        &mut frame,
+                        &self.previous_backgrounds,
+                        &self.backgrounds,
+                        *progress,
+                    );
+                }
+                None => {}
+            }
+
+            frame.commit();
+        }
+    }
+}
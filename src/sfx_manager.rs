@@ -1,48 +1,761 @@
 use agb::{
+    fixnum::Num,
     include_wav,
     sound::mixer::{ChannelId, Mixer, SoundChannel, SoundData},
 };
+use agb_tracker::{Track as Module, Tracker, include_xm};
+use alloc::{borrow::Cow, vec::Vec};
 
 static TITLE_MUSIC: SoundData = include_wav!("sfx/title_loop.wav");
-static GAME_MUSIC: SoundData = include_wav!("sfx/game_loop.wav");
 
-pub struct Sfx<'a> {
-    mixer: Mixer<'a>,
-    channel: Option<ChannelId>,
+// The game theme in modules of the same length and tempo, from the always
+// playing base to the layers added as the run heats up
+static GAME_LAYERS: [Module; 3] = [
+    include_xm!("sfx/game_base.xm"),
+    include_xm!("sfx/game_drums.xm"),
+    include_xm!("sfx/game_lead.xm"),
+];
+
+static SLASH: SoundData = include_wav!("sfx/slash.wav");
+static CLANG: SoundData = include_wav!("sfx/clang.wav");
+static WHOOSH: SoundData = include_wav!("sfx/whoosh.wav");
+static BUZZ: SoundData = include_wav!("sfx/buzz.wav");
+static TICK: SoundData = include_wav!("sfx/tick.wav");
+static JINGLE: SoundData = include_wav!("sfx/jingle.wav");
+
+pub const MAX_VOLUME: u8 = 8;
+
+// Music plays at this percentage of its volume while ducked
+const DUCK_PERCENT: i32 = 35;
+
+pub const CROSSFADE_FRAMES: usize = 30;
+
+// Volume levels from 0 to `MAX_VOLUME` for each category
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Volume {
+    pub music: u8,
+    pub effects: u8,
 }
-impl<'a> Sfx<'a> {
-    pub fn frame(&mut self) {
-        for _ in 0..200 {
-            self.mixer.frame();
+
+impl Volume {
+    pub const DEFAULT: Self = Self {
+        music: MAX_VOLUME,
+        effects: MAX_VOLUME,
+    };
+
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&[self.music, self.effects]);
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<(Self, &[u8])> {
+        let (&[music, effects], rest) = bytes.split_first_chunk::<2>()?;
+
+        if music > MAX_VOLUME || effects > MAX_VOLUME {
+            return None;
         }
+
+        Some((Self { music, effects }, rest))
     }
+}
+
+// Scales full volume by `level` out of `MAX_VOLUME` and by `percent`
+fn channel_volume(level: u8, percent: i32) -> Num<i16, 8> {
+    let full = 1 << 8;
+    Num::from_raw((full * level as i32 * percent / (MAX_VOLUME as i32 * 100)) as i16)
+}
+
+// What a channel is used for. Music is one looping track, effects share a
+// pool of channels by priority and UI sounds replace each other.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Role {
+    Music,
+    Effects,
+    Ui,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SfxError {
+    // The mixer had no channel left to play on
+    NoFreeChannel,
+    // Every effect channel is busy with something more important
+    Outranked,
+}
+
+// Effects never take more than this many mixer channels, leaving the rest
+// for music
+const MAX_EFFECTS: usize = 4;
+
+// How much a layer's volume moves each frame as it is added or removed
+const LAYER_FADE_STEP: i32 = 5;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Track {
+    Title,
+    Game,
+}
+
+// Music is a looping sample or tracker modules stepped in sync, each one a
+// layer of the track
+enum Source {
+    Sampled(SoundData),
+    Modules(&'static [Module]),
+}
 
-    pub fn play_title_theme(&mut self) {
-        let mut title_music = SoundChannel::new(TITLE_MUSIC);
-        title_music.should_loop();
+impl Track {
+    pub const ALL: [Track; 2] = [Track::Title, Track::Game];
 
-        self.channel = self.mixer.play_sound(title_music);
+    pub const fn name(self) -> &'static str {
+        match self {
+            Track::Title => "Title",
+            Track::Game => "Game",
+        }
+    }
+
+    // How many layers the track can play at once
+    pub fn layers(self) -> usize {
+        match self.source() {
+            Source::Sampled(_) => 1,
+            Source::Modules(modules) => modules.len(),
+        }
+    }
+
+    fn source(self) -> Source {
+        match self {
+            Track::Title => Source::Sampled(TITLE_MUSIC),
+            Track::Game => Source::Modules(&GAME_LAYERS),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SoundEffect {
+    Slash,
+    Clang,
+    Whoosh,
+    Buzz,
+    Tick,
+    Jingle,
+}
+
+impl SoundEffect {
+    pub const ALL: [SoundEffect; 6] = [
+        SoundEffect::Slash,
+        SoundEffect::Clang,
+        SoundEffect::Whoosh,
+        SoundEffect::Buzz,
+        SoundEffect::Tick,
+        SoundEffect::Jingle,
+    ];
+
+    pub const fn name(self) -> &'static str {
+        match self {
+            SoundEffect::Slash => "Slash",
+            SoundEffect::Clang => "Clang",
+            SoundEffect::Whoosh => "Whoosh",
+            SoundEffect::Buzz => "Buzz",
+            SoundEffect::Tick => "Tick",
+            SoundEffect::Jingle => "Jingle",
+        }
     }
 
-    pub fn play_game_theme(&mut self) {
-        let mut game_music = SoundChannel::new(GAME_MUSIC);
-        game_music.should_loop();
+    fn data(self) -> SoundData {
+        match self {
+            SoundEffect::Slash => SLASH,
+            SoundEffect::Clang => CLANG,
+            SoundEffect::Whoosh => WHOOSH,
+            SoundEffect::Buzz => BUZZ,
+            SoundEffect::Tick => TICK,
+            SoundEffect::Jingle => JINGLE,
+        }
+    }
 
-        self.channel = self.mixer.play_sound(game_music);
+    pub const fn role(self) -> Role {
+        match self {
+            SoundEffect::Slash | SoundEffect::Clang | SoundEffect::Whoosh | SoundEffect::Buzz => {
+                Role::Effects
+            }
+            SoundEffect::Tick | SoundEffect::Jingle => Role::Ui,
+        }
+    }
+
+    // When every effect channel is busy, an effect cuts off the lowest
+    // priority one playing, as long as that one is not more important
+    pub const fn priority(self) -> u8 {
+        match self {
+            SoundEffect::Slash | SoundEffect::Clang | SoundEffect::Whoosh => 1,
+            SoundEffect::Buzz | SoundEffect::Tick => 2,
+            SoundEffect::Jingle => 3,
+        }
+    }
+}
+
+// Mixer channels playing for each role
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ChannelUsage {
+    pub music: usize,
+    pub effects: usize,
+    pub ui: usize,
+}
+
+// A note started by a tracker. What the tracker asks of it is kept here and
+// passed on to the mixer channel after each step, so the music volume scales
+// the volume the module set rather than replacing it.
+struct TrackerNote {
+    // The sound to play, until the tracker starts it
+    channel: Option<SoundChannel>,
+    volume: Num<i16, 8>,
+    playback: Option<Num<u32, 8>>,
+    panning: Option<Num<i16, 8>>,
+    paused: Option<bool>,
+    stopped: bool,
+}
+
+impl agb_tracker::SoundChannel for TrackerNote {
+    fn new(data: &Cow<'static, [u8]>) -> Self {
+        Self {
+            channel: Some(<SoundChannel as agb_tracker::SoundChannel>::new(data)),
+            volume: Num::new(1),
+            playback: None,
+            panning: None,
+            paused: None,
+            stopped: false,
+        }
+    }
+
+    fn stop(&mut self) {
+        self.stopped = true;
+    }
+
+    fn pause(&mut self) -> &mut Self {
+        self.paused = Some(true);
+        self
+    }
+
+    fn resume(&mut self) -> &mut Self {
+        self.paused = Some(false);
+        self
+    }
+
+    // Only takes effect before the note starts
+    fn should_loop(&mut self) -> &mut Self {
+        if let Some(channel) = self.channel.as_mut() {
+            channel.should_loop();
+        }
+        self
+    }
+
+    fn volume(&mut self, value: impl Into<Num<i16, 8>>) -> &mut Self {
+        self.volume = value.into();
+        self
+    }
+
+    // Only takes effect before the note starts
+    fn restart_point(&mut self, value: impl Into<Num<u32, 8>>) -> &mut Self {
+        if let Some(channel) = self.channel.as_mut() {
+            channel.restart_point(value);
+        }
+        self
+    }
+
+    fn playback(&mut self, playback_speed: impl Into<Num<u32, 8>>) -> &mut Self {
+        self.playback = Some(playback_speed.into());
+        self
+    }
+
+    fn panning(&mut self, panning: impl Into<Num<i16, 8>>) -> &mut Self {
+        self.panning = Some(panning.into());
+        self
+    }
+}
+
+impl TrackerNote {
+    // Everything but the volume, which `Sfx::apply_music_volume` scales
+    fn apply(&mut self, channel: &mut SoundChannel) {
+        if let Some(playback) = self.playback.take() {
+            channel.playback(playback);
+        }
+        if let Some(panning) = self.panning.take() {
+            channel.panning(panning);
+        }
+        match self.paused.take() {
+            Some(true) => {
+                channel.pause();
+            }
+            Some(false) => {
+                channel.resume();
+            }
+            None => {}
+        }
+    }
+}
+
+// Passes a tracker's notes on to the mixer, keeping hold of the channels
+// they play on
+struct TrackedMixer<'m, 'a> {
+    mixer: &'m mut Mixer<'a>,
+    notes: &'m mut Vec<(ChannelId, TrackerNote)>,
+}
+
+impl agb_tracker::Mixer for TrackedMixer<'_, '_> {
+    type ChannelId = ChannelId;
+    type SoundChannel = TrackerNote;
+
+    fn channel(&mut self, channel_id: &ChannelId) -> Option<&mut TrackerNote> {
+        self.notes
+            .iter_mut()
+            .find(|(id, _)| id == channel_id)
+            .map(|(_, note)| note)
+    }
+
+    // Starts silent, the volume is set with the rest of the music
+    fn play_sound(&mut self, mut note: TrackerNote) -> Option<ChannelId> {
+        let mut channel = note.channel.take()?;
+        channel.volume(0);
+        note.apply(&mut channel);
+
+        let id = self.mixer.play_sound(channel)?;
+        self.notes.push((id, note));
+        Some(id)
+    }
+}
+
+// One module of the music. The tracker keeps stepping while the layer is
+// faded out, so it stays in sync with the others.
+struct Layer {
+    tracker: Tracker,
+    notes: Vec<(ChannelId, TrackerNote)>,
+    // Percentage of the music volume, moving towards 0 or 100
+    gain: i32,
+}
+
+impl Layer {
+    fn step(&mut self, mixer: &mut Mixer<'_>) {
+        // Notes that finished playing are released by the mixer
+        self.notes.retain(|(id, _)| mixer.channel(id).is_some());
+
+        self.tracker.step(&mut TrackedMixer {
+            mixer,
+            notes: &mut self.notes,
+        });
+
+        self.notes.retain_mut(|(id, note)| match mixer.channel(id) {
+            Some(channel) if note.stopped => {
+                channel.stop();
+                false
+            }
+            Some(channel) => {
+                note.apply(channel);
+                true
+            }
+            None => false,
+        });
+    }
+}
+
+enum Playback {
+    Sampled(ChannelId),
+    Modules(Vec<Layer>),
+}
+
+struct MusicChannel {
+    playback: Playback,
+    track: Track,
+}
+
+impl MusicChannel {
+    fn channels(&self) -> Vec<&ChannelId> {
+        match &self.playback {
+            Playback::Sampled(id) => alloc::vec![id],
+            Playback::Modules(layers) => layers
+                .iter()
+                .flat_map(|layer| layer.notes.iter().map(|(id, _)| id))
+                .collect(),
+        }
     }
 
+    fn step(&mut self, mixer: &mut Mixer<'_>) {
+        if let Playback::Modules(layers) = &mut self.playback {
+            for layer in layers {
+                layer.step(mixer);
+            }
+        }
+    }
+
+    // Sets the music to `level` out of `MAX_VOLUME` at `percent`, scaled by
+    // each layer's gain and each note's own volume
+    fn set_volume(&self, mixer: &mut Mixer<'_>, level: u8, percent: i32) {
+        match &self.playback {
+            Playback::Sampled(id) => {
+                if let Some(channel) = mixer.channel(id) {
+                    channel.volume(channel_volume(level, percent));
+                }
+            }
+            Playback::Modules(layers) => {
+                for layer in layers {
+                    let volume = channel_volume(level, percent * layer.gain / 100);
+                    for (id, note) in &layer.notes {
+                        if let Some(channel) = mixer.channel(id) {
+                            channel.volume(note.volume * volume);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn stop(self, mixer: &mut Mixer<'_>) {
+        for id in self.channels() {
+            if let Some(channel) = mixer.channel(id) {
+                channel.stop();
+            }
+        }
+    }
+}
+
+// The previous track fading out under the current one, which keeps playing
+// until it is silent
+struct Crossfade {
+    from: Option<MusicChannel>,
+    frame: usize,
+    frames: usize,
+}
+
+pub struct Sfx<'a> {
+    mixer: Mixer<'a>,
+    music: Option<MusicChannel>,
+    crossfade: Option<Crossfade>,
+    effects: Vec<(ChannelId, SoundEffect)>,
+    ui: Option<ChannelId>,
+    volume: Volume,
+    ducked: bool,
+    // How many layers play on top of the base of the music
+    intensity: usize,
+    // The last sound that failed to play, for the sound test
+    last_error: Option<SfxError>,
+}
+
+impl<'a> Sfx<'a> {
     pub fn create(mixer: Mixer<'a>) -> Self {
         Self {
             mixer,
-            channel: None,
+            music: None,
+            crossfade: None,
+            effects: Vec::new(),
+            ui: None,
+            volume: Volume::DEFAULT,
+            ducked: false,
+            intensity: 0,
+            last_error: None,
+        }
+    }
+
+    // Advances the music and mixes the next frame of audio. The scene manager
+    // calls this once per frame, straight after the vblank, so scenes never
+    // need to.
+    pub(crate) fn frame(&mut self) {
+        let fading = self
+            .crossfade
+            .as_mut()
+            .and_then(|crossfade| crossfade.from.as_mut());
+
+        for music in self.music.iter_mut().chain(fading) {
+            music.step(&mut self.mixer);
+        }
+
+        self.step_layers();
+        self.step_crossfade();
+
+        // Every frame, since layers fade and trackers change the volume of
+        // their notes
+        self.apply_music_volume();
+
+        self.mixer.frame();
+    }
+
+    // Mixes the next buffer of audio if the last one has started playing,
+    // without advancing the music. The mixer only buffers a frame ahead, so
+    // the scene manager calls this around work that can take longer than a
+    // frame, such as setting up a scene.
+    pub(crate) fn mix(&mut self) {
+        self.mixer.frame();
+    }
+
+    pub const fn volume(&self) -> Volume {
+        self.volume
+    }
+
+    // Applies to the music straight away and to effects from the next one
+    pub fn set_volume(&mut self, volume: Volume) {
+        self.volume = volume;
+        self.apply_music_volume();
+    }
+
+    // Lowers the music under menus and screens that want the focus
+    pub fn duck(&mut self, ducked: bool) {
+        self.ducked = ducked;
+        self.apply_music_volume();
+    }
+
+    pub fn playing(&self) -> Option<Track> {
+        self.music.as_ref().map(|music| music.track)
+    }
+
+    pub fn usage(&mut self) -> ChannelUsage {
+        self.effects
+            .retain(|(id, _)| self.mixer.channel(id).is_some());
+
+        let fading = self
+            .crossfade
+            .as_ref()
+            .and_then(|crossfade| crossfade.from.as_ref());
+
+        ChannelUsage {
+            music: self
+                .music
+                .iter()
+                .chain(fading)
+                .flat_map(MusicChannel::channels)
+                .filter(|&id| self.mixer.channel(id).is_some())
+                .count(),
+            effects: self.effects.len(),
+            ui: usize::from(
+                self.ui
+                    .as_ref()
+                    .is_some_and(|id| self.mixer.channel(id).is_some()),
+            ),
+        }
+    }
+
+    // Adds layers to the music up to `intensity` and fades out the rest
+    pub fn set_intensity(&mut self, intensity: usize) {
+        self.intensity = intensity;
+    }
+
+    fn step_layers(&mut self) {
+        let Some(MusicChannel {
+            playback: Playback::Modules(layers),
+            ..
+        }) = self.music.as_mut()
+        else {
+            return;
+        };
+
+        // The base layer always plays
+        for (index, layer) in layers.iter_mut().enumerate() {
+            let target = if index <= self.intensity { 100 } else { 0 };
+            layer.gain = if layer.gain < target {
+                (layer.gain + LAYER_FADE_STEP).min(target)
+            } else {
+                (layer.gain - LAYER_FADE_STEP).max(target)
+            };
+        }
+    }
+
+    fn is_playing(&self, track: Track) -> bool {
+        self.music
+            .as_ref()
+            .is_some_and(|music| music.track == track)
+    }
+
+    fn start_loop(&mut self, data: SoundData) -> Result<ChannelId, SfxError> {
+        let mut music = SoundChannel::new_high_priority(data);
+        music.should_loop().volume(0);
+
+        self.mixer.play_sound(music).ok_or(SfxError::NoFreeChannel)
+    }
+
+    fn start_music(&mut self, track: Track) -> Result<(), SfxError> {
+        let playback = match track.source() {
+            Source::Sampled(data) => Playback::Sampled(self.start_loop(data)?),
+            // Every module starts on the same frame and is stepped every
+            // frame after, so the layers stay in sync
+            Source::Modules(modules) => Playback::Modules(
+                modules
+                    .iter()
+                    .enumerate()
+                    .map(|(index, module)| Layer {
+                        tracker: Tracker::new(module),
+                        notes: Vec::new(),
+                        gain: if index <= self.intensity { 100 } else { 0 },
+                    })
+                    .collect(),
+            ),
+        };
+
+        self.music = Some(MusicChannel { playback, track });
+        Ok(())
+    }
+
+    // Replaces whatever music is playing, unless it is already this track
+    pub fn play_music(&mut self, track: Track) -> Result<(), SfxError> {
+        if self.is_playing(track) {
+            return Ok(());
+        }
+
+        self.stop(Role::Music);
+        let started = self.start_music(track);
+        self.apply_music_volume();
+        self.keep_error(started)
+    }
+
+    // Fades the current music out while `track` fades in
+    pub fn crossfade_to(&mut self, track: Track, frames: usize) -> Result<(), SfxError> {
+        if self.is_playing(track) {
+            return Ok(());
+        }
+
+        if let Some(from) = self.crossfade.take().and_then(|crossfade| crossfade.from) {
+            from.stop(&mut self.mixer);
+        }
+
+        self.crossfade = Some(Crossfade {
+            from: self.music.take(),
+            frame: 0,
+            frames,
+        });
+
+        let started = self.start_music(track);
+        self.apply_music_volume();
+        self.keep_error(started)
+    }
+
+    fn step_crossfade(&mut self) {
+        let Some(crossfade) = self.crossfade.as_mut() else {
+            return;
+        };
+
+        crossfade.frame += 1;
+        if crossfade.frame >= crossfade.frames
+            && let Some(from) = self.crossfade.take().and_then(|crossfade| crossfade.from)
+        {
+            from.stop(&mut self.mixer);
+        }
+    }
+
+    fn apply_music_volume(&mut self) {
+        let percent = if self.ducked { DUCK_PERCENT } else { 100 };
+        let level = self.volume.music;
+
+        let (fade_in, fade_out) = match &self.crossfade {
+            Some(crossfade) => {
+                let fade_in = (crossfade.frame * 100 / crossfade.frames.max(1)) as i32;
+                (fade_in, 100 - fade_in)
+            }
+            None => (100, 0),
+        };
+
+        if let Some(music) = &self.music {
+            music.set_volume(&mut self.mixer, level, percent * fade_in / 100);
+        }
+
+        if let Some(from) = self
+            .crossfade
+            .as_ref()
+            .and_then(|crossfade| crossfade.from.as_ref())
+        {
+            from.set_volume(&mut self.mixer, level, percent * fade_out / 100);
+        }
+    }
+
+    pub fn play_effect(&mut self, effect: SoundEffect) -> Result<(), SfxError> {
+        let played = self.start_effect(effect);
+        self.keep_error(played)
+    }
+
+    // Playing can fail whenever the mixer is busy, and the game carries on
+    // without the sound, so callers are free to ignore the error
+    fn keep_error(&mut self, result: Result<(), SfxError>) -> Result<(), SfxError> {
+        if let Err(error) = result {
+            self.last_error = Some(error);
         }
+        result
+    }
+
+    pub const fn last_error(&self) -> Option<SfxError> {
+        self.last_error
     }
 
-    pub fn stop(&mut self) {
+    fn start_effect(&mut self, effect: SoundEffect) -> Result<(), SfxError> {
+        match effect.role() {
+            Role::Ui => {
+                self.stop(Role::Ui);
+                self.ui = Some(self.play_once(effect)?);
+            }
+            _ => {
+                self.make_room_for(effect)?;
+                let id = self.play_once(effect)?;
+                self.effects.push((id, effect));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn play_once(&mut self, effect: SoundEffect) -> Result<ChannelId, SfxError> {
+        let mut channel = SoundChannel::new(effect.data());
+        channel.volume(channel_volume(self.volume.effects, 100));
+
         self.mixer
-            .channel(self.channel.as_ref().unwrap())
-            .unwrap()
-            .stop();
-        self.frame();
+            .play_sound(channel)
+            .ok_or(SfxError::NoFreeChannel)
+    }
+
+    fn make_room_for(&mut self, effect: SoundEffect) -> Result<(), SfxError> {
+        // Channels that finished playing are released by the mixer
+        self.effects
+            .retain(|(id, _)| self.mixer.channel(id).is_some());
+
+        if self.effects.len() < MAX_EFFECTS {
+            return Ok(());
+        }
+
+        let Some(lowest) = self
+            .effects
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, (_, playing))| playing.priority())
+            .map(|(index, _)| index)
+        else {
+            return Ok(());
+        };
+
+        if self.effects[lowest].1.priority() > effect.priority() {
+            return Err(SfxError::Outranked);
+        }
+
+        let (id, _) = self.effects.swap_remove(lowest);
+        if let Some(channel) = self.mixer.channel(&id) {
+            channel.stop();
+        }
+
+        Ok(())
+    }
+
+    // Stops everything playing in the role. Safe to call when nothing is
+    // playing or the sound already ended.
+    pub fn stop(&mut self, role: Role) {
+        match role {
+            Role::Music => {
+                let fading = self.crossfade.take().and_then(|crossfade| crossfade.from);
+
+                for music in fading.into_iter().chain(self.music.take()) {
+                    music.stop(&mut self.mixer);
+                }
+            }
+            Role::Ui => Self::stop_channel(&mut self.mixer, self.ui.take()),
+            Role::Effects => {
+                for (id, _) in self.effects.drain(..) {
+                    Self::stop_channel(&mut self.mixer, Some(id));
+                }
+            }
+        }
+    }
+
+    fn stop_channel(mixer: &mut Mixer<'a>, id: Option<ChannelId>) {
+        if let Some(id) = id
+            && let Some(channel) = mixer.channel(&id)
+        {
+            channel.stop();
+        }
     }
 }
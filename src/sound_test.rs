@@ -0,0 +1,185 @@
+use agb::{
+    display::{GraphicsFrame, font::AlignmentKind, tiled::BackgroundId},
+    fixnum::vec2,
+    input::Button,
+};
+use alloc::{boxed::Box, format, string::String, vec::Vec};
+
+use crate::{
+    fade::Fade,
+    label::Label,
+    scene::{Context, Scene, Transition},
+    sfx_manager::{ChannelUsage, Role, SfxError, SoundEffect, Track},
+    title_screen::TitleScene,
+};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Entry {
+    Music(Track),
+    Effect(SoundEffect),
+}
+
+impl Entry {
+    fn all() -> impl Iterator<Item = Entry> {
+        Track::ALL
+            .into_iter()
+            .map(Entry::Music)
+            .chain(SoundEffect::ALL.into_iter().map(Entry::Effect))
+    }
+
+    fn name(self) -> String {
+        match self {
+            Entry::Music(track) => format!("Music: {0}", track.name()),
+            Entry::Effect(effect) => format!("Sfx: {0}", effect.name()),
+        }
+    }
+}
+
+fn layout(selected: usize) -> Label {
+    let mut text = String::from("Sound test");
+    for (index, entry) in Entry::all().enumerate() {
+        text.push_str(if index == selected { "\n> " } else { "\n  " });
+        text.push_str(&entry.name());
+    }
+
+    Label::new(&text, vec2(8, 4), AlignmentKind::Left, 18, 112)
+}
+
+fn usage_label(usage: &ChannelUsage, intensity: usize, error: Option<SfxError>) -> Label {
+    let status = match error {
+        Some(error) => format!("{error:?}"),
+        None => String::from("none"),
+    };
+
+    Label::new(
+        &format!(
+            "Music {0} Effects {1} UI {2}\nLayers: {3}  L/R to change\nLast error: {4}",
+            usage.music,
+            usage.effects,
+            usage.ui,
+            intensity + 1,
+            status
+        ),
+        vec2(8, 122),
+        AlignmentKind::Left,
+        18,
+        224,
+    )
+}
+
+// Lists every track and effect so they can be checked on hardware. Up and
+// Down pick an entry, A plays it, B stops it, L and R change the layers of
+// layered music and START goes back to the title.
+pub struct SoundTestScene {
+    selected: usize,
+    intensity: usize,
+    label: Label,
+    usage: Option<Label>,
+}
+
+impl SoundTestScene {
+    pub fn new() -> Self {
+        Self {
+            selected: 0,
+            intensity: 0,
+            label: layout(0),
+            usage: None,
+        }
+    }
+
+    fn stop_all(ctx: &mut Context<'_>) {
+        ctx.sfx.stop(Role::Music);
+        ctx.sfx.stop(Role::Effects);
+        ctx.sfx.stop(Role::Ui);
+    }
+}
+
+impl Default for SoundTestScene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scene for SoundTestScene {
+    fn enter(&mut self, ctx: &mut Context<'_>) {
+        Self::stop_all(ctx);
+        ctx.sfx.set_intensity(self.intensity);
+    }
+
+    fn update(&mut self, ctx: &mut Context<'_>) -> Transition {
+        let input = &ctx.input;
+        let count = Entry::all().count();
+
+        if input.is_just_pressed(Button::START) {
+            return Transition::Fade(Box::new(TitleScene::new()), Fade::black());
+        }
+
+        if input.is_just_pressed(Button::UP) {
+            self.selected = (self.selected + count - 1) % count;
+            self.label = layout(self.selected);
+        }
+
+        if input.is_just_pressed(Button::DOWN) {
+            self.selected = (self.selected + 1) % count;
+            self.label = layout(self.selected);
+        }
+
+        let Some(entry) = Entry::all().nth(self.selected) else {
+            return Transition::Stay;
+        };
+
+        if input.is_just_pressed(Button::A) {
+            // Failures are kept by `Sfx` and shown as the last error
+            let played = match entry {
+                Entry::Music(track) => ctx.sfx.play_music(track),
+                Entry::Effect(effect) => ctx.sfx.play_effect(effect),
+            };
+            played.ok();
+        }
+
+        if input.is_just_pressed(Button::B) {
+            ctx.sfx.stop(match entry {
+                Entry::Music(_) => Role::Music,
+                Entry::Effect(effect) => effect.role(),
+            });
+        }
+
+        let max_intensity = ctx.sfx.playing().map_or(0, |track| track.layers() - 1);
+
+        if input.is_just_pressed(Button::L) {
+            self.intensity = self.intensity.saturating_sub(1);
+        }
+
+        if input.is_just_pressed(Button::R) {
+            self.intensity += 1;
+        }
+
+        // The track playing can change, and a new one may have fewer layers
+        self.intensity = self.intensity.min(max_intensity);
+
+        ctx.sfx.set_intensity(self.intensity);
+
+        // Channels free themselves when a sound ends, so this is rebuilt
+        // every frame rather than only after a button press
+        self.usage = Some(usage_label(
+            &ctx.sfx.usage(),
+            self.intensity,
+            ctx.sfx.last_error(),
+        ));
+
+        Transition::Stay
+    }
+
+    fn draw(&mut self, frame: &mut GraphicsFrame<'_>, _backgrounds: &mut Vec<BackgroundId>) {
+        self.label.draw(frame);
+
+        if let Some(usage) = self.usage.as_mut() {
+            usage.draw(frame);
+        }
+    }
+
+    fn exit(&mut self, ctx: &mut Context<'_>) {
+        Self::stop_all(ctx);
+        ctx.sfx.set_intensity(0);
+    }
+}
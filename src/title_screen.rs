@@ -1,49 +1,205 @@
 use agb::{
     display::{
-        Graphics, Priority,
-        tiled::{RegularBackground, RegularBackgroundSize, TileFormat, VRAM_MANAGER},
+        GraphicsFrame, Priority,
+        font::AlignmentKind,
+        tiled::{BackgroundId, RegularBackground, RegularBackgroundSize, TileFormat},
     },
-    include_background_gfx,
-    input::{Button, ButtonController},
-    rng,
+    fixnum::vec2,
+    input::Button,
 };
+use alloc::{boxed::Box, format, vec::Vec};
 
-use crate::sfx_manager::Sfx;
+use crate::{
+    background,
+    binding::Bindings,
+    fade::Fade,
+    game::{GameScene, NextRun},
+    high_score::HighScores,
+    label::Label,
+    mode::GameMode,
+    options::OptionsMenu,
+    scene::{Context, Scene, Transition},
+    sfx_manager::{CROSSFADE_FRAMES, Track, Volume},
+    sound_test::SoundTestScene,
+};
+
+fn high_score_label(mode: GameMode, high_scores: &HighScores) -> Label {
+    let mut text = format!("{}\nHigh scores", mode.name());
+    for (rank, entry) in high_scores.entries().iter().enumerate() {
+        if entry.is_empty() {
+            text.push_str(&format!("\n{}. ---", rank + 1));
+        } else {
+            text.push_str(&format!(
+                "\n{0}. {1} {2}",
+                rank + 1,
+                entry.name(),
+                mode.format_score(entry.score)
+            ));
+        }
+    }
+
+    Label::new(&text, vec2(120 - 40, 8), AlignmentKind::Centre, 18, 80)
+}
+
+fn mode_label(mode: GameMode) -> Label {
+    Label::new(
+        &format!("< {} >", mode.name()),
+        vec2(120 - 60, 144),
+        AlignmentKind::Centre,
+        16,
+        120,
+    )
+}
+
+pub struct TitleScene {
+    map: RegularBackground,
+    // The time spent on the title screen seeds the first run
+    frames: u32,
+    table: Option<Label>,
+    options: Option<OptionsMenu>,
+    mode_text: Option<Label>,
+    saved_bindings: Bindings,
+    saved_volume: Volume,
+}
+
+impl TitleScene {
+    pub fn new() -> Self {
+        let mut map = RegularBackground::new(
+            Priority::P0,
+            RegularBackgroundSize::Background32x32,
+            TileFormat::FourBpp,
+        );
+
+        map.fill_with(&background::TITLE_SCREEN);
+
+        Self {
+            map,
+            frames: 0,
+            table: None,
+            options: None,
+            mode_text: None,
+            saved_bindings: Bindings::new(),
+            saved_volume: Volume::DEFAULT,
+        }
+    }
+
+    fn store_if_changed(&mut self, ctx: &mut Context<'_>) {
+        if ctx.save_game.bindings != self.saved_bindings
+            || ctx.save_game.volume != self.saved_volume
+        {
+            ctx.storage.store(&ctx.save_game);
+            self.saved_bindings = ctx.save_game.bindings.clone();
+            self.saved_volume = ctx.save_game.volume;
+        }
+    }
+}
+
+impl Default for TitleScene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-include_background_gfx!(
-    mod title_screen,
-    TITLE_SCREEN => deduplicate "gfx/title-screen.png",
-);
+impl Scene for TitleScene {
+    fn enter(&mut self, ctx: &mut Context<'_>) {
+        ctx.sfx.crossfade_to(Track::Title, CROSSFADE_FRAMES).ok();
 
-pub fn show_title_screen(gfx: &mut Graphics, sfx: &mut Sfx) {
-    let mut map = RegularBackground::new(
-        Priority::P3,
-        RegularBackgroundSize::Background32x32,
-        TileFormat::FourBpp,
-    );
+        self.saved_bindings = ctx.save_game.bindings.clone();
+        self.saved_volume = ctx.save_game.volume;
+        self.mode_text = Some(mode_label(ctx.mode));
+    }
 
-    sfx.play_title_theme();
+    fn update(&mut self, ctx: &mut Context<'_>) -> Transition {
+        self.frames = self.frames.wrapping_add(1);
 
-    let mut input = ButtonController::new();
+        let input = &ctx.input;
 
-    map.fill_with(&title_screen::TITLE_SCREEN);
+        if let Some(menu) = self.options.as_mut() {
+            let volume = ctx.save_game.volume;
+            let closed = menu.update(
+                input,
+                &mut ctx.save_game.bindings,
+                &mut ctx.save_game.volume,
+            );
 
-    VRAM_MANAGER.set_background_palettes(title_screen::PALETTES);
+            if ctx.save_game.volume != volume {
+                ctx.sfx.set_volume(ctx.save_game.volume);
+            }
+
+            // Stored straight away, rather than when leaving the title
+            // screen, in case the console is switched off first
+            if closed {
+                self.options = None;
+                self.store_if_changed(ctx);
+            }
+            return Transition::Stay;
+        }
 
-    loop {
-        sfx.frame();
-        input.update();
+        // Hidden sound test for checking the audio on hardware
+        if input.is_pressed(Button::L)
+            && input.is_pressed(Button::R)
+            && input.is_just_pressed(Button::B)
+        {
+            return Transition::Fade(Box::new(SoundTestScene::new()), Fade::cross());
+        }
 
         if input.is_just_pressed(Button::START) {
-            break;
+            return Transition::Fade(
+                Box::new(GameScene::new(NextRun::Seed(self.frames), ctx)),
+                Fade::cross(),
+            );
+        }
+
+        let mode = ctx.mode;
+
+        if input.is_just_pressed(Button::SELECT) {
+            self.table = match self.table {
+                Some(_) => None,
+                None => Some(high_score_label(mode, ctx.save_game.high_scores(mode))),
+            };
+        }
+
+        if input.is_just_pressed(Button::LEFT) || input.is_just_pressed(Button::RIGHT) {
+            ctx.mode = if input.is_just_pressed(Button::LEFT) {
+                mode.previous()
+            } else {
+                mode.next()
+            };
+            self.mode_text = Some(mode_label(ctx.mode));
+
+            if self.table.is_some() {
+                self.table = Some(high_score_label(
+                    ctx.mode,
+                    ctx.save_game.high_scores(ctx.mode),
+                ));
+            }
         }
 
-        let mut frame = gfx.frame();
-        map.show(&mut frame);
+        if input.is_just_pressed(Button::A) {
+            self.table = None;
+            self.options = Some(OptionsMenu::new(
+                &ctx.save_game.bindings,
+                ctx.save_game.volume,
+            ));
+        }
+
+        Transition::Stay
+    }
+
+    fn draw(&mut self, frame: &mut GraphicsFrame<'_>, backgrounds: &mut Vec<BackgroundId>) {
+        backgrounds.push(self.map.show(frame));
 
-        frame.commit();
+        if let Some(menu) = self.options.as_mut() {
+            menu.draw(frame);
+            return;
+        }
 
-        // Make the random number generator harder to predict
-        let _ = rng::next_i32();
+        if let Some(table) = self.table.as_mut() {
+            table.draw(frame);
+        }
+
+        if let Some(mode_text) = self.mode_text.as_mut() {
+            mode_text.draw(frame);
+        }
     }
 }